@@ -0,0 +1,198 @@
+//! Allocators for the memory of a scope, see [`crate::BoxScope::new_in`].
+
+use core::{alloc::Layout, ptr::NonNull};
+
+/// Allocates and frees the memory of a scope.
+///
+/// A [`crate::BoxScope`] stores its allocator, and uses it to free its memory when it is dropped.
+/// By default, scopes are allocated with the [`Global`] allocator.
+///
+/// This trait is a stable counterpart to the unstable [`core::alloc::Allocator`] trait, and can be implemented
+/// for region or bump allocators, or for references to them.
+///
+/// # Safety
+///
+/// - A pointer returned by [`ScopeAllocator::allocate`] must point to a block of memory that is valid
+///   for reads and writes of `layout.size()` bytes, aligned to `layout.align()`, and that is not otherwise used
+///   until it is passed to [`ScopeAllocator::deallocate`].
+/// - Moving the allocator must not invalidate the blocks of memory it allocated.
+///
+/// # Example
+///
+/// ```
+/// use core::{alloc::Layout, cell::Cell, ptr::NonNull};
+/// use nolife::{scope, BoxScope, Global, ScopeAllocator, SingleFamily};
+///
+/// /// Counts the live allocations of the global allocator.
+/// struct Counting<'a>(&'a Cell<usize>);
+///
+/// unsafe impl ScopeAllocator for Counting<'_> {
+///     fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+///         self.0.set(self.0.get() + 1);
+///         Global.allocate(layout)
+///     }
+///
+///     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+///         self.0.set(self.0.get() - 1);
+///         unsafe { Global.deallocate(ptr, layout) }
+///     }
+/// }
+///
+/// let live = Cell::new(0);
+/// let mut scope = BoxScope::<SingleFamily<u32>, _>::new_in(
+///     scope!({ freeze_forever!(&mut 42) }),
+///     Counting(&live),
+/// );
+/// assert_eq!(scope.enter(|x| *x), 42);
+/// assert_eq!(live.get(), 1);
+///
+/// drop(scope);
+/// assert_eq!(live.get(), 0);
+/// ```
+pub unsafe trait ScopeAllocator {
+    /// Allocates a block of memory for `layout`, or returns `None` if the allocation failed.
+    ///
+    /// Scopes never request blocks of memory with a size of zero.
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>>;
+
+    /// Frees the block of memory pointed to by `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// - `ptr` was returned by a call to [`ScopeAllocator::allocate`] on this allocator, with the same `layout`.
+    /// - `ptr` was not already freed.
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+}
+
+// SAFETY: forwards to `A`, that upholds the contract of `ScopeAllocator`.
+unsafe impl<A: ScopeAllocator + ?Sized> ScopeAllocator for &A {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        (**self).allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        // SAFETY: forwarded from the caller.
+        unsafe { (**self).deallocate(ptr, layout) }
+    }
+}
+
+/// The global memory allocator, used by scopes by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Global;
+
+// SAFETY: forwards to the global allocator, that upholds the contract of `ScopeAllocator`.
+unsafe impl ScopeAllocator for Global {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        if layout.size() == 0 {
+            // the global allocator does not support zero-sized allocations, and they don't need any memory,
+            // so like `NonNull::dangling`, return a pointer that is only aligned.
+            return NonNull::new(layout.align() as *mut u8);
+        }
+        // SAFETY: `layout` has a non-zero size.
+        NonNull::new(unsafe { alloc::alloc::alloc(layout) })
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() == 0 {
+            return;
+        }
+        // SAFETY: `ptr` was allocated by the global allocator with `layout`, as per the precondition.
+        unsafe { alloc::alloc::dealloc(ptr.as_ptr(), layout) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{scope, BoxScope, Never, SingleFamily};
+    use core::{cell::Cell, future::Future};
+
+    /// Counts the live allocations of the global allocator.
+    #[derive(Default)]
+    struct Counting {
+        live: Cell<usize>,
+    }
+
+    unsafe impl ScopeAllocator for Counting {
+        fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+            self.live.set(self.live.get() + 1);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.live.set(self.live.get() - 1);
+            unsafe { Global.deallocate(ptr, layout) }
+        }
+    }
+
+    #[test]
+    fn new_dyn_in() {
+        let allocator = Counting::default();
+        let mut scope: BoxScope<SingleFamily<u32>, dyn Future<Output = Never>, (), (), &Counting> =
+            BoxScope::new_dyn_in(
+                scope!({
+                    let mut x = 0u32;
+                    loop {
+                        freeze!(&mut x);
+                        x += 1;
+                    }
+                }),
+                &allocator,
+            );
+        assert_eq!(allocator.live.get(), 1);
+        assert_eq!(scope.enter(|x| *x), 0);
+        assert_eq!(scope.enter(|x| *x), 1);
+
+        drop(scope);
+        assert_eq!(allocator.live.get(), 0);
+    }
+
+    #[test]
+    fn replace_reallocates() {
+        let allocator = Counting::default();
+        let mut scope: BoxScope<SingleFamily<u32>, dyn Future<Output = Never>, (), (), &Counting> =
+            BoxScope::new_dyn_in(scope!({ freeze_forever!(&mut 0) }), &allocator);
+        assert_eq!(scope.enter(|x| *x), 0);
+
+        let large = [1u8; 256];
+        scope.replace(scope!({
+            let mut sum: u32 = large.iter().map(|x| u32::from(*x)).sum();
+            freeze_forever!(&mut sum)
+        }));
+        assert_eq!(allocator.live.get(), 1);
+        assert_eq!(scope.enter(|x| *x), 256);
+
+        drop(scope);
+        assert_eq!(allocator.live.get(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn panic_while_opening() {
+        let allocator = Counting::default();
+        assert!(std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+            BoxScope::<SingleFamily<u32>, _>::new_in(
+                unsafe {
+                    crate::scope::new_scope(|_time_capsule| {
+                        panic!("panicking producer");
+                        #[allow(unreachable_code, clippy::empty_loop)]
+                        async {
+                            loop {}
+                        }
+                    })
+                },
+                &allocator,
+            )
+        }))
+        .is_err());
+        assert_eq!(allocator.live.get(), 0);
+    }
+
+    #[test]
+    fn zero_sized() {
+        let layout = Layout::new::<()>();
+        let ptr = Global.allocate(layout).unwrap();
+        // SAFETY: `ptr` was allocated by `Global` with `layout`.
+        unsafe { Global.deallocate(ptr, layout) };
+    }
+}
@@ -0,0 +1,358 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex, PoisonError, TryLockError,
+};
+
+use core::{cell::UnsafeCell, future::Future, mem::MaybeUninit, ptr::NonNull};
+
+use crate::{raw_scope::RawScope, AlreadyEntered, Family, Never, TopScope};
+
+// SAFETY: repr C to ensure conversion between ArcInner<T, MaybeUninit<F>> and ArcInner<T, F>
+// does not rely on unstable memory layout.
+#[repr(C)]
+struct ArcInner<T, F: ?Sized>
+where
+    T: for<'a> Family<'a>,
+{
+    lock: Mutex<()>,
+    /// Mirrors the poisoned state of `raw_scope`, so that it can be read without taking `lock`.
+    poisoned: AtomicBool,
+    raw_scope: UnsafeCell<RawScope<T, F>>,
+}
+
+/// A thread-safe dynamic scope tied to an atomically reference-counted allocation.
+///
+/// Cloning an `ArcScope` is cheap and yields a handle to the same scope.
+/// Entering the scope locks it, so that a single thread at a time can access the frozen data.
+///
+/// An `ArcScope` is [`Send`] and [`Sync`] when both its future and the frozen data are [`Send`].
+/// See the [counterexamples](crate::counterexamples#arcscope-is-not-send-when-the-future-is-not-send)
+/// for why these bounds are required.
+pub struct ArcScope<T, F: ?Sized = dyn Future<Output = Never> + Send + 'static>(
+    Arc<ArcInner<T, F>>,
+)
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>;
+
+// SAFETY:
+// - Sending an `ArcScope` to another thread can cause the future to be polled or dropped on that thread,
+//   so we require `F: Send`.
+// - The frozen data is accessed by the thread that enters the scope, so we require the family to be `Send`.
+unsafe impl<T, F: ?Sized> Send for ArcScope<T, F>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never> + Send,
+    for<'a> <T as Family<'a>>::Family: Send,
+{
+}
+
+// SAFETY: all accesses to the scope through a shared reference are serialized by the lock,
+// so like for `Mutex`, sharing the scope between threads is sound if it can be sent between threads.
+unsafe impl<T, F: ?Sized> Sync for ArcScope<T, F>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never> + Send,
+    for<'a> <T as Family<'a>>::Family: Send,
+{
+}
+
+impl<T, F: ?Sized> Clone for ArcScope<T, F>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> ArcScope<T>
+where
+    T: for<'a> Family<'a>,
+{
+    /// Ties the passed scope to an atomically reference-counted allocation.
+    ///
+    /// This function erased the `Future` generic type of the [`TopScope`], at the cost
+    /// of using a dynamic function call to poll the future.
+    ///
+    /// If the `Future` generic type can be inferred, it can be more efficient to use [`ArcScope::new`].
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics.
+    pub fn new_dyn<S: TopScope<Family = T>>(scope: S) -> Self
+    where
+        S::Future: Send + 'static,
+    {
+        let this = ArcScope::new(scope);
+        Self(this.0)
+    }
+}
+
+impl<T, F> ArcScope<T, F>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    /// Ties the passed scope to an atomically reference-counted allocation.
+    ///
+    /// This function retains the `Future` generic type from the [`TopScope`].
+    /// To store the [`ArcScope`] in a struct, it can be easier to use [`ArcScope::new_dyn`].
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics.
+    pub fn new<S: TopScope<Family = T, Future = F>>(scope: S) -> ArcScope<T, F> {
+        let inner = Arc::new(ArcInner {
+            lock: Mutex::new(()),
+            poisoned: AtomicBool::new(false),
+            raw_scope: UnsafeCell::new(RawScope::<T, F>::new_uninit(())),
+        });
+        let inner: *const ArcInner<T, MaybeUninit<F>> = Arc::into_raw(inner);
+        struct Guard<T, F>
+        where
+            T: for<'a> Family<'a>,
+        {
+            inner: *const ArcInner<T, MaybeUninit<F>>,
+        }
+        // guard ensures the Arc is freed on panic (i.e. if scope.run panics)
+        let panic_guard = Guard { inner };
+        impl<T, F> Drop for Guard<T, F>
+        where
+            T: for<'a> Family<'a>,
+        {
+            fn drop(&mut self) {
+                // SAFETY: defuse below makes sure this only happens on panic,
+                // in this case, self.inner is still in the same uninitialized state
+                // and not otherwise being cleaned up, so this `Arc::from_raw` pairs with
+                // `Arc::into_raw` above
+                drop(unsafe { Arc::from_raw(self.inner) })
+            }
+        }
+
+        // SAFETY: `inner` comes from `Arc::into_raw` and so points to a live `ArcInner`.
+        let raw_scope: *mut RawScope<T, F> = unsafe { (*inner).raw_scope.get() }.cast();
+
+        // SAFETY:
+        // 1. `raw_scope` allocated by the `Arc` so is valid memory, although the future is not yet initialized
+        // 2. `raw_scope` was created from a valid `RawScope::<T, MaybeUninit<F>>`, so `state` is fully initialized.
+        //
+        // Note: as a post-condition of `RawScope`, `raw_scope` is fully initialized.
+        unsafe {
+            RawScope::open(raw_scope, scope);
+        }
+
+        core::mem::forget(panic_guard); // defuse guard
+
+        // SAFETY: this `Arc::from_raw` pairs with the `Arc::into_raw` above.
+        // The type `F` is not the same, but `MaybeUninit<F>` and `F` are repr(transparent)-compatible
+        // and `ArcInner` and `RawScope` are repr(C), so both types have the same size and alignment.
+        // Furthermore, `RawScope::open` ensured that F is properly initialized so it may be dropped.
+        ArcScope(unsafe { Arc::from_raw(inner.cast()) })
+    }
+}
+
+impl<T, F: ?Sized> ArcScope<T, F>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    /// Enters the scope, making it possible to access the data frozen inside of the scope.
+    ///
+    /// If the scope is already entered by another thread, blocks the current thread until it is available.
+    ///
+    /// A panic while the scope is entered poisons the scope, but does not poison the lock:
+    /// later calls to this function panic without blocking, see [`ArcScope::is_poisoned`].
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    /// - If the scope is poisoned, see [`ArcScope::is_poisoned`].
+    /// - If the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    ///
+    /// Calling this function while the current thread already entered the scope
+    /// (e.g. from `f`) might panic or deadlock, use [`ArcScope::try_enter`] instead.
+    pub fn enter<Output, G>(&self, f: G) -> Output
+    where
+        G: for<'borrow, 'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        let _guard = self.0.lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+        // SAFETY: the lock is held
+        unsafe { self.enter_locked(f) }
+    }
+
+    /// Attempts to enter the scope, making it possible to access the data frozen inside of the scope.
+    ///
+    /// This function does not block.
+    ///
+    /// # Errors
+    ///
+    /// - [`AlreadyEntered`] if the scope is currently entered, by this thread or another one.
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    /// - If the scope is poisoned, see [`ArcScope::is_poisoned`].
+    /// - If the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    pub fn try_enter<Output, G>(&self, f: G) -> Result<Output, AlreadyEntered>
+    where
+        G: for<'borrow, 'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        let _guard = match self.0.lock.try_lock() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => return Err(AlreadyEntered),
+        };
+
+        // SAFETY: the lock is held
+        Ok(unsafe { self.enter_locked(f) })
+    }
+
+    /// # Safety
+    ///
+    /// 1. `self.0.lock` is held for the duration of the call.
+    unsafe fn enter_locked<Output, G>(&self, f: G) -> Output
+    where
+        G: for<'borrow, 'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        // SAFETY: `UnsafeCell::get` never returns a null pointer.
+        let raw_scope = unsafe { NonNull::new_unchecked(self.0.raw_scope.get()) };
+
+        // publishes the poisoned state of the scope once it is left, even if `f` or the future panicked.
+        struct PoisonGuard<'a, T, F: ?Sized>
+        where
+            T: for<'b> Family<'b>,
+            F: Future<Output = Never>,
+        {
+            poisoned: &'a AtomicBool,
+            raw_scope: NonNull<RawScope<T, F>>,
+        }
+        impl<T, F: ?Sized> Drop for PoisonGuard<'_, T, F>
+        where
+            T: for<'b> Family<'b>,
+            F: Future<Output = Never>,
+        {
+            fn drop(&mut self) {
+                // SAFETY: the scope is valid, and the lock is still held by the caller of `enter_locked`.
+                let poisoned = unsafe { RawScope::is_poisoned(self.raw_scope) };
+                self.poisoned.store(poisoned, Ordering::Release);
+            }
+        }
+        let _poison_guard = PoisonGuard {
+            poisoned: &self.0.poisoned,
+            raw_scope,
+        };
+
+        // SAFETY:
+        // 1. `raw_scope` is valid as a post-condition of `new`.
+        // 2. The object pointed to by `raw_scope` is owned by the `Arc` and won't move before deallocation.
+        // 3. precondition (1) guarantees that no other call to `RawScope::enter` is in progress,
+        //    and the reference passed to `f` cannot escape `f`.
+        // 4. `new` only accepts scopes with `()` as input.
+        unsafe { RawScope::enter(raw_scope, (), f) }.into_entered()
+    }
+
+    /// Returns `true` if the scope is poisoned.
+    ///
+    /// A scope becomes poisoned when its underlying future panics, or when a function passed to enter the scope panics,
+    /// as the frozen data might then be left in an inconsistent state.
+    /// Entering a poisoned scope panics.
+    ///
+    /// This function does not lock the scope, so it can be called while the scope is entered,
+    /// by this thread or another one. A scope that is currently entered is reported as poisoned
+    /// only once the call that poisoned it returned or panicked.
+    pub fn is_poisoned(&self) -> bool {
+        self.0.poisoned.load(Ordering::Acquire)
+    }
+
+    /// Returns `true` if the two `ArcScope`s are handles to the same scope.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{scope, SingleFamily};
+
+    #[test]
+    fn enter_from_threads() {
+        let scope = ArcScope::<SingleFamily<u32>>::new_dyn(scope!({
+            let mut x = 0u32;
+            loop {
+                freeze!(&mut x);
+                x += 1;
+            }
+        }));
+
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..10 {
+                        scope.enter(|x| *x);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(scope.enter(|x| *x), 40);
+    }
+
+    #[test]
+    fn send_to_thread() {
+        let scope = ArcScope::<SingleFamily<String>, _>::new(scope!({
+            let mut s = String::from("Intel the Beagle");
+            freeze_forever!(&mut s)
+        }));
+        let other = scope.clone();
+
+        let len = std::thread::spawn(move || other.enter(|s| s.len()))
+            .join()
+            .unwrap();
+
+        assert_eq!(len, 16);
+        assert!(ArcScope::ptr_eq(&scope, &scope.clone()));
+    }
+
+    #[test]
+    fn try_enter_while_entered() {
+        let scope = ArcScope::<SingleFamily<u32>>::new_dyn(scope!({ freeze_forever!(&mut 42) }));
+
+        assert_eq!(
+            scope.enter(|x| {
+                assert_eq!(*x, 42);
+                scope.try_enter(|_| ())
+            }),
+            Err(AlreadyEntered)
+        );
+        assert_eq!(scope.try_enter(|x| *x), Ok(42));
+    }
+
+    #[test]
+    fn poisoned_after_panic() {
+        let scope = ArcScope::<SingleFamily<u32>>::new_dyn(scope!({ freeze_forever!(&mut 42) }));
+
+        assert!(std::panic::catch_unwind(core::panic::AssertUnwindSafe(
+            || scope.enter(|_| panic!())
+        ))
+        .is_err());
+        assert!(scope.is_poisoned());
+        assert!(
+            std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| scope.enter(|x| *x)))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn is_poisoned_while_entered() {
+        let scope = ArcScope::<SingleFamily<u32>>::new_dyn(scope!({ freeze_forever!(&mut 42) }));
+        let other = scope.clone();
+
+        assert!(!scope.enter(|_| other.is_poisoned()));
+    }
+}
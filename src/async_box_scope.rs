@@ -0,0 +1,337 @@
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{raw_scope::RawScope, BoxScope, Family, Never, TopScope};
+
+/// A dynamic scope tied to a Box, whose future can await arbitrary futures between two freezes.
+///
+/// Unlike [`BoxScope`], where awaiting any future other than a frozen future panics,
+/// entering an `AsyncBoxScope` returns a future that passes the [`Context`] of the caller down to the scope.
+/// This allows the scope to `.await` asynchronous operations, such as I/O, before freezing its data.
+///
+/// `I` is the type of the inputs passed to the scope when resuming it, see [`AsyncBoxScope::enter_with`].
+///
+/// # Example
+///
+/// ```
+/// use nolife::{scope, AsyncBoxScope, SingleFamily};
+///
+/// async fn fetch(x: u32) -> u32 {
+///     x
+/// }
+///
+/// # fn block_on<F: std::future::Future>(fut: F) -> F::Output {
+/// #     let mut fut = std::pin::pin!(fut);
+/// #     let waker = std::task::Waker::noop();
+/// #     loop {
+/// #         if let std::task::Poll::Ready(ready) = fut.as_mut().poll(&mut std::task::Context::from_waker(&waker)) {
+/// #             return ready;
+/// #         }
+/// #     }
+/// # }
+/// block_on(async {
+///     let mut scope = AsyncBoxScope::<SingleFamily<u32>>::new_dyn(scope!({
+///         let mut x = fetch(42).await;
+///         loop {
+///             freeze!(&mut x);
+///             x += fetch(1).await;
+///         }
+///     }));
+///
+///     assert_eq!(scope.enter(|x| *x).await, 42);
+///     assert_eq!(scope.enter(|x| *x).await, 43);
+/// });
+/// ```
+pub struct AsyncBoxScope<T, F: ?Sized = dyn Future<Output = Never> + 'static, I = ()>(
+    BoxScope<T, F, I>,
+)
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>;
+
+impl<'env, T, I> AsyncBoxScope<T, dyn Future<Output = Never> + 'env, I>
+where
+    T: for<'a> Family<'a>,
+{
+    /// Ties the passed scope to the heap.
+    ///
+    /// This function erased the `Future` generic type of the [`TopScope`], at the cost
+    /// of using a dynamic function call to poll the future.
+    ///
+    /// If the `Future` generic type can be inferred, it can be more efficient to use [`AsyncBoxScope::new`].
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics.
+    pub fn new_dyn<S: TopScope<I, Family = T>>(scope: S) -> Self
+    where
+        S::Future: 'env,
+    {
+        Self(BoxScope::new_dyn(scope))
+    }
+}
+
+impl<'env, T, I> AsyncBoxScope<T, dyn Future<Output = Never> + Send + 'env, I>
+where
+    T: for<'a> Family<'a>,
+{
+    /// Ties the passed scope to the heap, keeping the information that its future is [`Send`].
+    ///
+    /// Like [`AsyncBoxScope::new_dyn`], this function erases the `Future` generic type of the [`TopScope`],
+    /// but the resulting scope, and the futures entering it, are [`Send`] if its frozen data is [`Send`].
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics.
+    pub fn new_dyn_send<S: TopScope<I, Family = T>>(scope: S) -> Self
+    where
+        S::Future: Send + 'env,
+    {
+        Self(BoxScope::new_dyn_send(scope))
+    }
+}
+
+impl<T, F, I> AsyncBoxScope<T, F, I>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    /// Ties the passed scope to the heap.
+    ///
+    /// This function retains the `Future` generic type from the [`TopScope`].
+    /// To store the [`AsyncBoxScope`] in a struct, it can be easier to use [`AsyncBoxScope::new_dyn`].
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics.
+    pub fn new<S: TopScope<I, Family = T, Future = F>>(scope: S) -> Self {
+        Self(BoxScope::new(scope))
+    }
+}
+
+impl<T, F: ?Sized, I> AsyncBoxScope<T, F, I>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    /// Returns `true` if the scope is poisoned, see [`BoxScope::is_poisoned`].
+    pub fn is_poisoned(&self) -> bool {
+        self.0.is_poisoned()
+    }
+}
+
+impl<T, F: ?Sized> AsyncBoxScope<T, F>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    /// Enters the scope, making it possible to access the data frozen inside of the scope.
+    ///
+    /// The returned future resumes the scope, and resolves once the scope is frozen again.
+    /// While the scope awaits other futures, the returned future is pending.
+    ///
+    /// # Cancellation
+    ///
+    /// Dropping the returned future before it resolves does not lose the progress of the scope:
+    /// the next call to `enter` continues where the scope stopped, see [`AsyncEnter`].
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    /// - If the scope is poisoned, see [`AsyncBoxScope::is_poisoned`].
+    /// - If the underlying future froze its data using `freeze_ref!`.
+    pub fn enter<'borrow, Output, G>(&'borrow mut self, f: G) -> AsyncEnter<'borrow, T, F, (), G>
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        self.enter_with((), f)
+    }
+}
+
+impl<T, F: ?Sized, I> AsyncBoxScope<T, F, I>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    /// Enters the scope, passing `input` to the scope before accessing the data frozen inside of the scope.
+    ///
+    /// See [`AsyncBoxScope::enter`] and [`BoxScope::enter_with`].
+    ///
+    /// # Cancellation
+    ///
+    /// The input is passed to the scope the first time the returned future is polled.
+    /// If the returned future is dropped while pending, the scope already received its input,
+    /// and stopped part-way to its next freeze. The next call to `enter_with` first finishes that resume,
+    /// so the scope reaches its next freeze without receiving the new `input`, that is dropped.
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    /// - If the scope is poisoned, see [`AsyncBoxScope::is_poisoned`].
+    /// - If the underlying future froze its data using `freeze_ref!`.
+    pub fn enter_with<'borrow, Output, G>(
+        &'borrow mut self,
+        input: I,
+        f: G,
+    ) -> AsyncEnter<'borrow, T, F, I, G>
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        AsyncEnter {
+            scope: &mut self.0,
+            input: Some(input),
+            f: Some(f),
+        }
+    }
+}
+
+/// The future returned by [`AsyncBoxScope::enter`] and [`AsyncBoxScope::enter_with`].
+///
+/// # Cancellation
+///
+/// Dropping an `AsyncEnter` that is pending leaves the scope part-way through the resume,
+/// waiting on the future it awaited. The next call to [`AsyncBoxScope::enter`] or [`AsyncBoxScope::enter_with`]
+/// completes that resume instead of starting a new one: the function passed to that call accesses the data of
+/// the next freeze, and the input passed to that call is dropped without being read by the scope.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct AsyncEnter<'borrow, T, F: ?Sized, I, G>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    scope: &'borrow mut BoxScope<T, F, I>,
+    input: Option<I>,
+    f: Option<G>,
+}
+
+// The scope is pinned by its allocation, and neither the input nor the function are ever pinned.
+impl<T, F: ?Sized, I, G> Unpin for AsyncEnter<'_, T, F, I, G>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+}
+
+impl<'borrow, T, F: ?Sized, I, G, Output> Future for AsyncEnter<'borrow, T, F, I, G>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+    G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+{
+    type Output = Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        assert!(this.f.is_some(), "`AsyncEnter` polled after completion");
+        let raw_scope = this.scope.0;
+
+        // SAFETY:
+        // 1. `raw_scope` is valid as a post-condition of `BoxScope::new`.
+        // 2. The object pointed to by `raw_scope` did not move and won't before deallocation.
+        // 3. `this.scope` is an exclusive reference for `'borrow`, and the references passed to previous
+        //    calls to `f` cannot outlive the borrow of the scope that created them.
+        // 4. `BoxScope::new` only accepts scopes with `I` as input.
+        let result = match unsafe { RawScope::poll(raw_scope, &mut this.input, cx) } {
+            Poll::Ready(Ok(())) => {
+                let f = this.f.take().unwrap();
+                // SAFETY:
+                // 1. `raw_scope` is valid as a post-condition of `BoxScope::new`.
+                // 2. `this.scope` is an exclusive reference for `'borrow`, and `f` is only called once,
+                //    so the reference passed to `f` is the only one for `'borrow`.
+                unsafe { RawScope::access(raw_scope, f) }
+            }
+            Poll::Ready(Err(error)) => Err(error),
+            Poll::Pending => return Poll::Pending,
+        };
+
+        match result {
+            Ok(outcome) => Poll::Ready(outcome.into_entered()),
+            // SAFETY: `raw_scope` is valid as a post-condition of `BoxScope::new`.
+            Err(error) => unsafe { RawScope::fail(raw_scope, "enter", error) },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{scope, SingleFamily};
+    use core::task::Waker;
+
+    /// A future that is pending the first time it is polled, waking its waker.
+    struct YieldNow(bool);
+
+    impl Future for YieldNow {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    fn poll_once<Fut: Future + Unpin>(fut: &mut Fut) -> Poll<Fut::Output> {
+        Pin::new(fut).poll(&mut Context::from_waker(Waker::noop()))
+    }
+
+    #[test]
+    fn await_between_freezes() {
+        let mut scope = AsyncBoxScope::<SingleFamily<u32>>::new_dyn(scope!({
+            let mut x = 0u32;
+            loop {
+                YieldNow(false).await;
+                freeze!(&mut x);
+                x += 1;
+            }
+        }));
+
+        let mut enter = scope.enter(|x| *x);
+        assert_eq!(poll_once(&mut enter), Poll::Pending);
+        assert_eq!(poll_once(&mut enter), Poll::Ready(0));
+
+        let mut enter = scope.enter(|x| *x);
+        assert_eq!(poll_once(&mut enter), Poll::Pending);
+        assert_eq!(poll_once(&mut enter), Poll::Ready(1));
+    }
+
+    #[test]
+    fn cancelled_enter() {
+        let mut scope = AsyncBoxScope::<SingleFamily<u32>, _, u32>::new(scope!(input: u32, {
+            let mut x = 0u32;
+            loop {
+                let input = freeze!(&mut x);
+                YieldNow(false).await;
+                x += input;
+            }
+        }));
+
+        assert_eq!(poll_once(&mut scope.enter_with(0, |x| *x)), Poll::Ready(0));
+        assert_eq!(poll_once(&mut scope.enter_with(42, |x| *x)), Poll::Pending);
+        // the scope continues where it stopped, the input of the cancelled call was used, and `1` is dropped
+        assert_eq!(poll_once(&mut scope.enter_with(1, |x| *x)), Poll::Ready(42));
+    }
+
+    #[test]
+    fn send_enter() {
+        fn assert_send<S: Send>(s: S) -> S {
+            s
+        }
+
+        let mut scope = AsyncBoxScope::<SingleFamily<u32>, _>::new_dyn_send(scope!({
+            YieldNow(false).await;
+            freeze_forever!(&mut 42)
+        }));
+        let mut enter = assert_send(scope.enter(|x| *x));
+        assert_eq!(poll_once(&mut enter), Poll::Pending);
+        assert_eq!(poll_once(&mut enter), Poll::Ready(42));
+    }
+}
@@ -1,45 +1,109 @@
-use alloc::boxed::Box;
 use core::{
+    alloc::Layout,
     future::Future,
+    marker::PhantomData,
     mem::{self, MaybeUninit},
-    ptr::NonNull,
+    panic::Location,
+    ptr::{self, NonNull},
 };
 
-use crate::{raw_scope::RawScope, Family, Never, TopScope};
+use crate::{
+    allocator::{Global, ScopeAllocator},
+    observer::ScopeObserver,
+    raw_scope::RawScope,
+    EnterOutcome, Family, Never, ResumeOutcome, Scope, ScopeError, ScopeState, TopScope,
+};
 
 /// A dynamic scope tied to a Box.
 ///
 /// This kind of scopes uses a dynamic allocation.
 /// In exchange, it is fully `'static` and can be moved after creation.
-#[repr(transparent)]
-pub struct BoxScope<T, F: ?Sized = dyn Future<Output = Never> + 'static>(
-    core::ptr::NonNull<RawScope<T, F>>,
+///
+/// The scope is typically a [`TopScope`], that never finishes. Scopes with another output are supported,
+/// see [`BoxScope::enter_finite`].
+///
+/// `I` is the type of the inputs passed to the scope when resuming it, see [`BoxScope::enter_with`].
+///
+/// `Obs` is the observer notified of the lifecycle events of the scope, see [`BoxScope::new_observed`].
+///
+/// `A` is the allocator of the memory of the scope, see [`BoxScope::new_in`].
+pub struct BoxScope<
+    T,
+    F: ?Sized = dyn Future<Output = Never> + 'static,
+    I = (),
+    Obs = (),
+    A = Global,
+>(
+    pub(crate) core::ptr::NonNull<RawScope<T, F, F::Output, Obs>>,
+    A,
+    PhantomData<fn(I)>,
 )
 where
     T: for<'a> Family<'a>,
-    F: Future<Output = Never>;
+    F: Future,
+    Obs: ScopeObserver,
+    A: ScopeAllocator;
 
-impl<T, F: ?Sized> Drop for BoxScope<T, F>
+// SAFETY:
+// - Sending a `BoxScope` to another thread causes the future to be polled or dropped on that thread,
+//   so we require `F: Send`.
+// - The frozen data is accessed by the thread that enters the scope, so we require the family to be `Send`.
+// - The output of the future, the observer and the allocator are stored in the scope, so we require them to be `Send`.
+unsafe impl<T, F: ?Sized, I, Obs, A> Send for BoxScope<T, F, I, Obs, A>
 where
     T: for<'a> Family<'a>,
-    F: Future<Output = Never>,
+    F: Future + Send,
+    F::Output: Send,
+    Obs: ScopeObserver + Send,
+    A: ScopeAllocator + Send,
+    for<'a> <T as Family<'a>>::Family: Send,
+{
+}
+
+// SAFETY: the future and its output are never accessed through a shared reference to a `BoxScope`,
+// but the frozen data, the observer and the allocator might be, so we require them to be `Sync`.
+unsafe impl<T, F: ?Sized, I, Obs, A> Sync for BoxScope<T, F, I, Obs, A>
+where
+    T: for<'a> Family<'a>,
+    F: Future,
+    Obs: ScopeObserver + Sync,
+    A: ScopeAllocator + Sync,
+    for<'a> <T as Family<'a>>::Family: Sync,
+{
+}
+
+impl<T, F: ?Sized, I, Obs, A> Drop for BoxScope<T, F, I, Obs, A>
+where
+    T: for<'a> Family<'a>,
+    F: Future,
+    Obs: ScopeObserver,
+    A: ScopeAllocator,
 {
     fn drop(&mut self) {
-        // SAFETY: this `Box::from_raw` pairs with a `Box::into_raw`
-        // in the `new` constructor. The type `F` is not the same,
-        // but `MaybeUninit<F>` and `F` are repr(transparent)-compatible
-        // and RawScope is repr(C), so the Box frees the same memory.
-        // Furthermore, the `new` constructor ensured that F is properly
+        // SAFETY: the scope is valid as a post-condition of `new`, and `&mut self` guarantees exclusive access.
+        unsafe { RawScope::notify_drop(self.0) };
+
+        // SAFETY: the scope is valid as a post-condition of `new`.
+        let layout = Layout::for_value(unsafe { self.0.as_ref() });
+
+        // SAFETY: the `new` constructor ensured that F is properly
         // initialized so it may be dropped.
         //
-        // Finally, the drop order of implicitly first dropping self.0.state
+        // The drop order of first dropping self.0.state
         // and THEN self.0.active_fut goes a bit against the typical self-referencing
         // structs assumptions, however self.0.state is a pointer and has no drop glue.
-        drop(unsafe { Box::from_raw(self.0.as_ptr()) })
+        // self.0.output and self.0.observer are dropped in between, but they cannot borrow from self.0.active_fut.
+        unsafe { ptr::drop_in_place(self.0.as_ptr()) };
+
+        // SAFETY: this deallocation pairs with the allocation in the `new_in` constructor,
+        // with the same allocator. The type `F` is not the same,
+        // but `MaybeUninit<F>` and `F` are repr(transparent)-compatible
+        // and RawScope is repr(C), so `layout` is the layout of the allocation.
+        unsafe { self.1.deallocate(self.0.cast(), layout) }
     }
 }
 
-impl<T> BoxScope<T>
+impl<'env, T, I> BoxScope<T, dyn Future<Output = Never> + 'env, I>
 where
     T: for<'a> Family<'a>,
 {
@@ -50,54 +114,373 @@ where
     ///
     /// If the `Future` generic type can be inferred, it can be more efficient to use [`BoxScope::new`].
     ///
+    /// The erased future can borrow from its environment for `'env`, so that a scope borrowing its inputs
+    /// can be stored in a struct with a lifetime parameter.
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nolife::{scope, BoxScope, Never, SingleFamily, TopScope};
+    /// use std::future::Future;
+    ///
+    /// struct Words<'text> {
+    ///     scope: BoxScope<SingleFamily<usize>, dyn Future<Output = Never> + 'text>,
+    /// }
+    ///
+    /// fn count_words<'text>(text: &'text str) -> impl TopScope<Family = SingleFamily<usize>> + 'text {
+    ///     scope!({ freeze_forever!(&mut text.split_whitespace().count()) })
+    /// }
+    ///
+    /// let text = "Intel the Beagle".to_string();
+    /// let mut words = Words {
+    ///     scope: BoxScope::new_dyn(count_words(&text)),
+    /// };
+    /// assert_eq!(words.scope.enter(|count| *count), 3);
+    /// ```
+    pub fn new_dyn<S: TopScope<I, Family = T>>(scope: S) -> Self
+    where
+        S::Future: 'env,
+    {
+        BoxScope::new(scope).into_dyn()
+    }
+
+    /// Ties the passed scope to memory allocated by `allocator`, erasing its `Future` generic type.
+    ///
+    /// See [`BoxScope::new_dyn`] and [`BoxScope::new_in`].
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics.
+    /// - If `allocator` fails to allocate the memory of the scope.
+    pub fn new_dyn_in<S: TopScope<I, Family = T>, A: ScopeAllocator>(
+        scope: S,
+        allocator: A,
+    ) -> BoxScope<T, dyn Future<Output = Never> + 'env, I, (), A>
+    where
+        S::Future: 'env,
+    {
+        BoxScope::new_in(scope, allocator).into_dyn()
+    }
+}
+
+impl<'env, T, I, Obs, A> BoxScope<T, dyn Future<Output = Never> + 'env, I, Obs, A>
+where
+    T: for<'a> Family<'a>,
+    Obs: ScopeObserver,
+    A: ScopeAllocator,
+{
+    /// Replaces the scope with the passed scope, erasing its `Future` generic type.
+    ///
+    /// The previous future is dropped in place. If the layout of the new scope is the same as the layout
+    /// of the previous one, which is the case when their futures have the same type,
+    /// the new scope reuses the allocation of the previous one. Otherwise, the new scope is allocated with
+    /// the allocator of the scope, and the previous allocation is freed.
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics. The scope is left unchanged.
+    /// - If dropping the previous future panics. The scope is replaced regardless.
+    /// - If the allocator fails to allocate the memory of the new scope.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nolife::{scope, BoxScope, SingleFamily, TopScope};
+    ///
+    /// fn count_words(text: String) -> impl TopScope<Family = SingleFamily<usize>> {
+    ///     scope!({ freeze_forever!(&mut text.split_whitespace().count()) })
+    /// }
+    ///
+    /// let mut scope: BoxScope<SingleFamily<usize>> = BoxScope::new_dyn(count_words("Intel".to_string()));
+    /// assert_eq!(scope.enter(|count| *count), 1);
+    ///
+    /// scope.replace(count_words("Intel the Beagle".to_string()));
+    /// assert_eq!(scope.enter(|count| *count), 3);
+    /// ```
+    pub fn replace<S: TopScope<I, Family = T>>(&mut self, scope: S)
+    where
+        S::Future: 'env,
+    {
+        self.replace_with(scope, |raw_scope| raw_scope)
+    }
+}
+
+impl<'env, T, I, Obs, A> BoxScope<T, dyn Future<Output = Never> + Send + 'env, I, Obs, A>
+where
+    T: for<'a> Family<'a>,
+    Obs: ScopeObserver,
+    A: ScopeAllocator,
+{
+    /// Replaces the scope with the passed scope, erasing its `Future` generic type while keeping it [`Send`].
+    ///
+    /// The previous future is dropped in place. If the layout of the new scope is the same as the layout
+    /// of the previous one, the new scope reuses the allocation of the previous one.
+    /// Otherwise, the new scope is allocated with the allocator of the scope, and the previous allocation is freed.
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics. The scope is left unchanged.
+    /// - If dropping the previous future panics. The scope is replaced regardless.
+    /// - If the allocator fails to allocate the memory of the new scope.
+    pub fn replace<S: TopScope<I, Family = T>>(&mut self, scope: S)
+    where
+        S::Future: Send + 'env,
+    {
+        self.replace_with(scope, |raw_scope| raw_scope)
+    }
+}
+
+impl<'env, T, I> BoxScope<T, dyn Future<Output = Never> + Send + 'env, I>
+where
+    T: for<'a> Family<'a>,
+{
+    /// Ties the passed scope to the heap, keeping the information that its future is [`Send`].
+    ///
+    /// Like [`BoxScope::new_dyn`], this function erases the `Future` generic type of the [`TopScope`],
+    /// but the resulting scope is [`Send`] if its frozen data is [`Send`].
+    ///
     /// # Panics
     ///
     /// - If `scope` panics.
-    pub fn new_dyn<S: TopScope<Family = T>>(scope: S) -> Self
+    pub fn new_dyn_send<S: TopScope<I, Family = T>>(scope: S) -> Self
     where
-        S::Future: 'static,
+        S::Future: Send + 'env,
     {
-        let this = mem::ManuallyDrop::new(BoxScope::new(scope));
-        Self(this.0)
+        BoxScope::new(scope).into_dyn_send()
     }
 }
 
-impl<T, F> BoxScope<T, F>
+impl<T, F, I, Obs, A> BoxScope<T, F, I, Obs, A>
 where
     T: for<'a> Family<'a>,
     F: Future<Output = Never>,
+    Obs: ScopeObserver,
+    A: ScopeAllocator,
+{
+    /// Erases the `Future` generic type of the scope, at the cost of using a dynamic function call to poll the future.
+    ///
+    /// Unlike [`BoxScope::new_dyn`], this function applies to a scope that was already opened,
+    /// and neither moves nor resumes it. Like [`BoxScope::new_dyn`], the erased future can borrow
+    /// from its environment for `'env`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nolife::{scope, BoxScope, SingleFamily};
+    ///
+    /// let mut scope = BoxScope::<SingleFamily<u32>, _>::new(scope!({
+    ///     let mut x = 0u32;
+    ///     loop {
+    ///         freeze!(&mut x);
+    ///         x += 1;
+    ///     }
+    /// }));
+    /// assert_eq!(scope.enter(|x| *x), 0);
+    ///
+    /// let mut scope: BoxScope<SingleFamily<u32>> = scope.into_dyn();
+    /// assert_eq!(scope.enter(|x| *x), 1);
+    /// ```
+    pub fn into_dyn<'env>(self) -> BoxScope<T, dyn Future<Output = Never> + 'env, I, Obs, A>
+    where
+        F: 'env,
+    {
+        let this = mem::ManuallyDrop::new(self);
+        // SAFETY: `this` is never dropped, so the allocator is moved out exactly once.
+        let allocator = unsafe { ptr::read(&this.1) };
+        BoxScope(this.0, allocator, PhantomData)
+    }
+
+    /// Erases the `Future` generic type of the scope, keeping the information that its future is [`Send`].
+    ///
+    /// See [`BoxScope::into_dyn`] and [`BoxScope::new_dyn_send`].
+    pub fn into_dyn_send<'env>(
+        self,
+    ) -> BoxScope<T, dyn Future<Output = Never> + Send + 'env, I, Obs, A>
+    where
+        F: Send + 'env,
+    {
+        let this = mem::ManuallyDrop::new(self);
+        // SAFETY: `this` is never dropped, so the allocator is moved out exactly once.
+        let allocator = unsafe { ptr::read(&this.1) };
+        BoxScope(this.0, allocator, PhantomData)
+    }
+}
+
+impl<T, F, I, Obs, A> BoxScope<T, F, I, Obs, A>
+where
+    T: for<'a> Family<'a>,
+    F: Future,
+    Obs: ScopeObserver,
+    A: ScopeAllocator,
+{
+    /// Replaces the scope with the passed scope, reusing its allocation.
+    ///
+    /// The previous future is dropped in place, and the future of the new scope is stored in its place.
+    /// The observer of the scope is kept, and the new scope is not run until it is entered.
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics. The scope is left unchanged.
+    /// - If dropping the previous future panics. The scope is replaced regardless.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nolife::{scope, BoxScope, SingleFamily, TopScope};
+    ///
+    /// fn count_words(text: String) -> impl TopScope<Family = SingleFamily<usize>> {
+    ///     scope!({ freeze_forever!(&mut text.split_whitespace().count()) })
+    /// }
+    ///
+    /// let mut scope = BoxScope::new(count_words("Intel".to_string()));
+    /// assert_eq!(scope.enter(|count| *count), 1);
+    ///
+    /// scope.replace(count_words("Intel the Beagle".to_string()));
+    /// assert_eq!(scope.enter(|count| *count), 3);
+    /// ```
+    pub fn replace<S: Scope<I, Family = T, Future = F>>(&mut self, scope: S) {
+        self.replace_with(scope, |raw_scope| raw_scope)
+    }
+}
+
+impl<T, F, I> BoxScope<T, F, I>
+where
+    T: for<'a> Family<'a>,
+    F: Future,
 {
     /// Ties the passed scope to the heap.
     ///
-    /// This function retains the `Future` generic type from the [`TopScope`].
+    /// This function retains the `Future` generic type from the [`Scope`].
     /// To store the [`BoxScope`] in a struct, it can be easier to use [`BoxScope::new_dyn`].
     ///
     /// # Panics
     ///
     /// - If `scope` panics.
-    pub fn new<S: TopScope<Family = T, Future = F>>(scope: S) -> BoxScope<T, F> {
-        let raw_scope = Box::new(RawScope::<T, F>::new_uninit());
-        let raw_scope: *mut RawScope<T, MaybeUninit<F>> = Box::into_raw(raw_scope);
-        struct Guard<Sc> {
+    pub fn new<S: Scope<I, Family = T, Future = F>>(scope: S) -> BoxScope<T, F, I> {
+        BoxScope::new_observed(scope, ())
+    }
+
+    /// Ties the passed scope to the heap, failing to compile if its future is larger than `MAX_BYTES`.
+    ///
+    /// Besides this check, this function is the same as [`BoxScope::new`].
+    /// See [`Scope::FUTURE_LAYOUT`] to inspect the layout of the future of a scope.
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nolife::{scope, BoxScope, SingleFamily};
+    ///
+    /// let mut scope = BoxScope::<SingleFamily<u32>, _>::new_bounded::<64>(scope!({
+    ///     freeze_forever!(&mut 42)
+    /// }));
+    /// assert_eq!(scope.enter(|x| *x), 42);
+    /// ```
+    ///
+    /// A scope whose future exceeds its budget fails to compile:
+    ///
+    /// ```compile_fail,E0080
+    /// use nolife::{scope, BoxScope, SingleFamily};
+    ///
+    /// let mut scope = BoxScope::<SingleFamily<[u8; 1024]>, _>::new_bounded::<64>(scope!({
+    ///     let mut buffer = [0u8; 1024];
+    ///     freeze_forever!(&mut buffer)
+    /// }));
+    /// ```
+    pub fn new_bounded<const MAX_BYTES: usize>(
+        scope: impl Scope<I, Family = T, Future = F>,
+    ) -> BoxScope<T, F, I> {
+        const {
+            assert!(
+                Layout::new::<F>().size() <= MAX_BYTES,
+                "the future of the scope exceeds MAX_BYTES"
+            )
+        };
+        BoxScope::new(scope)
+    }
+
+    /// Ties the passed scope to the heap, notifying `observer` of the lifecycle events of the scope.
+    ///
+    /// The hooks of `observer` are called each time the scope is resumed, frozen or entered,
+    /// see [`ScopeObserver`]. The observer can be accessed with [`BoxScope::observer`].
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics.
+    pub fn new_observed<S: Scope<I, Family = T, Future = F>, Obs: ScopeObserver>(
+        scope: S,
+        observer: Obs,
+    ) -> BoxScope<T, F, I, Obs> {
+        BoxScope::open_in(scope, observer, Global)
+    }
+
+    /// Ties the passed scope to memory allocated by `allocator`.
+    ///
+    /// The allocator is stored in the scope, and frees its memory when the scope is dropped.
+    /// See [`ScopeAllocator`] for an example.
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics.
+    /// - If `allocator` fails to allocate the memory of the scope.
+    pub fn new_in<S: Scope<I, Family = T, Future = F>, A: ScopeAllocator>(
+        scope: S,
+        allocator: A,
+    ) -> BoxScope<T, F, I, (), A> {
+        BoxScope::open_in(scope, (), allocator)
+    }
+
+    fn open_in<S: Scope<I, Family = T, Future = F>, Obs: ScopeObserver, A: ScopeAllocator>(
+        scope: S,
+        observer: Obs,
+        allocator: A,
+    ) -> BoxScope<T, F, I, Obs, A> {
+        let layout = Layout::new::<RawScope<T, MaybeUninit<F>, F::Output, Obs>>();
+        let raw_scope: *mut RawScope<T, MaybeUninit<F>, F::Output, Obs> = allocator
+            .allocate(layout)
+            .unwrap_or_else(|| alloc::alloc::handle_alloc_error(layout))
+            .cast()
+            .as_ptr();
+        // SAFETY: `raw_scope` was allocated with the layout of a `RawScope<T, MaybeUninit<F>, F::Output, Obs>`.
+        unsafe { raw_scope.write(RawScope::<T, F, F::Output, Obs>::new_uninit(observer)) };
+
+        struct Guard<'a, Sc, A: ScopeAllocator> {
             raw_scope: *mut Sc,
+            allocator: &'a A,
         }
-        // guard ensures Box is freed on panic (i.e. if scope.run panics)
-        let panic_guard = Guard { raw_scope };
-        impl<Sc> Drop for Guard<Sc> {
+        // guard ensures the memory is freed on panic (i.e. if scope.run panics)
+        let panic_guard = Guard {
+            raw_scope,
+            allocator: &allocator,
+        };
+        impl<Sc, A: ScopeAllocator> Drop for Guard<'_, Sc, A> {
             fn drop(&mut self) {
                 // SAFETY: defuse below makes sure this only happens on panic,
                 // in this case, self.raw_scope is still in the same uninitialized state
-                // and not otherwise being cleaned up, so this `Box::from_raw` pairs with
-                // `Box::into_raw` above
-                drop(unsafe { Box::from_raw(self.raw_scope) })
+                // and not otherwise being cleaned up, so this pairs with the allocation above.
+                unsafe {
+                    ptr::drop_in_place(self.raw_scope);
+                    self.allocator.deallocate(
+                        NonNull::new_unchecked(self.raw_scope).cast(),
+                        Layout::new::<Sc>(),
+                    )
+                }
             }
         }
 
-        let raw_scope: *mut RawScope<T, F> = raw_scope.cast();
+        let raw_scope: *mut RawScope<T, F, F::Output, Obs> = raw_scope.cast();
 
         // SAFETY:
-        // 1. `raw_scope` allocated by the `Box` so is valid memory, although the future is not yet initialized
-        // 2. `raw_scope` was created from a valid `RawScope::<T, MaybeUninit<F>>`, so `state` is fully initialized.
+        // 1. `raw_scope` allocated by the allocator so is valid memory, although the future is not yet initialized
+        // 2. `raw_scope` was created from a valid `RawScope::<T, MaybeUninit<F>, F::Output, Obs>`,
+        //    so `state`, `output` and `observer` are fully initialized.
         //
         // Note: as a post-condition of `RawScope`, `raw_scope` is fully initialized.
         unsafe {
@@ -107,31 +490,775 @@ where
         mem::forget(panic_guard); // defuse guard
                                   // (guard field has no drop glue, so this does not leak anything, it just skips the above `Drop` impl)
 
-        // SAFETY: `raw_scope` allocated by the `Box` so is non-null.
-        BoxScope(unsafe { NonNull::new_unchecked(raw_scope) })
+        // SAFETY: `raw_scope` allocated by the allocator so is non-null.
+        BoxScope(
+            unsafe { NonNull::new_unchecked(raw_scope) },
+            allocator,
+            PhantomData,
+        )
+    }
+}
+
+impl<T, F, I, O, E> BoxScope<T, F, I>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Result<O, E>>,
+{
+    /// Ties the passed scope to the heap, and runs it up to its first freeze.
+    ///
+    /// Unlike [`BoxScope::new`], the setup code of the scope runs immediately, so that failures
+    /// can be reported by the constructor:
+    /// if the scope returns an error before its first freeze, this error is returned.
+    ///
+    /// The first call to enter the scope accesses the value of the first freeze without resuming the scope.
+    /// As the scope can still return an error later, use [`BoxScope::enter_finite`] to access the frozen data.
+    ///
+    /// # Errors
+    ///
+    /// - The error returned by the scope, if it returns an error before its first freeze.
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics.
+    /// - If the underlying future panics.
+    /// - If the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nolife::{scope, BoxScope, EnterOutcome, Family};
+    ///
+    /// struct Document<'a> {
+    ///     words: Vec<&'a str>,
+    /// }
+    ///
+    /// struct DocumentFamily;
+    ///
+    /// impl<'a> Family<'a> for DocumentFamily {
+    ///     type Family = Document<'a>;
+    /// }
+    ///
+    /// fn parse(text: String) -> Result<BoxScope<DocumentFamily, impl std::future::Future<Output = Result<nolife::Never, String>>>, String> {
+    ///     BoxScope::try_new(scope!({
+    ///         if text.is_empty() {
+    ///             return Err("empty document".to_string());
+    ///         }
+    ///         let mut document = Document { words: text.split_whitespace().collect() };
+    ///         loop {
+    ///             freeze!(&mut document);
+    ///         }
+    ///     }))
+    /// }
+    ///
+    /// assert_eq!(parse(String::new()).err(), Some("empty document".to_string()));
+    ///
+    /// let mut document = parse("Intel the Beagle".to_string()).unwrap();
+    /// assert!(matches!(document.enter_finite(|document| document.words.len()), EnterOutcome::Entered(3)));
+    /// ```
+    pub fn try_new<S: Scope<I, Family = T, Future = F>>(scope: S) -> Result<BoxScope<T, F, I>, E> {
+        let this = BoxScope::new(scope);
+
+        // SAFETY:
+        // 1. `this.0` is valid as a post-condition of `new`.
+        // 2. The object pointed to by `this.0` did not move and won't before deallocation.
+        // 3. `this` was just created, so no reference to its frozen value or output exists, and it was never polled.
+        // 4. `new` only accepts scopes with `I` as input.
+        if let Err(error) = unsafe { RawScope::prime::<I>(this.0) } {
+            // SAFETY: `this.0` is valid as a post-condition of `new`.
+            unsafe { RawScope::fail(this.0, "open", error) }
+        }
+
+        // SAFETY:
+        // 1. `this.0` is valid as a post-condition of `new`.
+        // 2. No reference to the output exists.
+        match unsafe { RawScope::take_error(this.0) } {
+            Some(error) => Err(error),
+            None => Ok(this),
+        }
     }
 }
 
-impl<T, F: ?Sized> BoxScope<T, F>
+impl<T, F: ?Sized, Obs, A> BoxScope<T, F, (), Obs, A>
 where
     T: for<'a> Family<'a>,
     F: Future<Output = Never>,
+    Obs: ScopeObserver,
+    A: ScopeAllocator,
 {
     /// Enters the scope, making it possible to access the data frozen inside of the scope.
     ///
+    /// For scopes that can finish, use [`BoxScope::enter_finite`].
+    ///
     /// # Panics
     ///
     /// - If the passed function panics.
     /// - If the underlying future panics.
+    /// - If the scope is poisoned, see [`BoxScope::is_poisoned`].
     /// - If the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    /// - If the underlying future froze its data using `freeze_ref!`, see [`BoxScope::enter_shared`].
     pub fn enter<'borrow, Output, G>(&'borrow mut self, f: G) -> Output
     where
         G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
     {
+        self.enter_with((), f)
+    }
+
+    /// Attempts to enter the scope, making it possible to access the data frozen inside of the scope.
+    ///
+    /// Unlike [`BoxScope::enter`], this function returns an error instead of panicking when the scope cannot be entered.
+    ///
+    /// # Errors
+    ///
+    /// - [`ScopeError::Poisoned`] if the scope is poisoned, see [`BoxScope::is_poisoned`].
+    /// - [`ScopeError::ForeignFuture`] if the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    /// - [`ScopeError::NotFilled`] if the underlying future did not fill the value.
+    /// - [`ScopeError::FrozenShared`] if the underlying future froze its data using `freeze_ref!`,
+    ///   see [`BoxScope::enter_shared`].
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nolife::{scope, BoxScope, ScopeError, SingleFamily};
+    ///
+    /// let mut scope = BoxScope::<SingleFamily<u32>>::new_dyn(scope!({ freeze_forever!(&mut 42) }));
+    ///
+    /// assert_eq!(scope.try_enter(|x| *x), Ok(42));
+    ///
+    /// let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    ///     scope.enter(|_| panic!("oops"));
+    /// }));
+    /// assert!(panicked.is_err());
+    ///
+    /// assert!(scope.is_poisoned());
+    /// assert_eq!(scope.try_enter(|x| *x), Err(ScopeError::Poisoned));
+    /// ```
+    pub fn try_enter<'borrow, Output, G>(&'borrow mut self, f: G) -> Result<Output, ScopeError>
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        self.try_enter_with((), f)
+    }
+
+    /// Enters the scope, blocking the current thread while the underlying future awaits other futures.
+    ///
+    /// Unlike [`BoxScope::enter`], the underlying future may await any future, not only the [`crate::FrozenFuture`].
+    /// While such a future is pending, the current thread is parked until the future wakes it.
+    /// This makes it possible to call asynchronous code from a scope without an executor.
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    /// - If the scope is poisoned, see [`BoxScope::is_poisoned`].
+    /// - If the underlying future froze its data using `freeze_ref!`, see [`BoxScope::enter_shared`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nolife::{scope, BoxScope, SingleFamily};
+    ///
+    /// async fn fetch(x: u32) -> u32 {
+    ///     x
+    /// }
+    ///
+    /// let mut scope = BoxScope::<SingleFamily<u32>>::new_dyn(scope!({
+    ///     let mut x = fetch(42).await;
+    ///     freeze_forever!(&mut x)
+    /// }));
+    ///
+    /// assert_eq!(scope.enter_blocking(|x| *x), 42);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn enter_blocking<'borrow, Output, G>(&'borrow mut self, f: G) -> Output
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        self.enter_blocking_with((), f)
+    }
+}
+
+impl<T, F: ?Sized, I, Obs, A> BoxScope<T, F, I, Obs, A>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+    Obs: ScopeObserver,
+    A: ScopeAllocator,
+{
+    /// Enters the scope, passing `input` to the scope before accessing the data frozen inside of the scope.
+    ///
+    /// The `freeze!` call where the scope is currently frozen evaluates to `input` when the scope resumes.
+    /// If the scope was never entered before, it is not frozen yet and `input` is dropped.
+    ///
+    /// Scopes accepting an input are created by passing the type of the input to the [`crate::scope!`] macro.
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    /// - If the scope is poisoned, see [`BoxScope::is_poisoned`].
+    /// - If the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    /// - If the underlying future froze its data using `freeze_ref!`, see [`BoxScope::enter_shared`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nolife::{scope, BoxScope, SingleFamily};
+    ///
+    /// let mut scope = BoxScope::<SingleFamily<u32>, _, u32>::new(scope!(input: u32, {
+    ///     let mut total = 0;
+    ///     loop {
+    ///         let x = freeze!(&mut total);
+    ///         total += x;
+    ///     }
+    /// }));
+    ///
+    /// assert_eq!(scope.enter_with(1, |total| *total), 0); // no `freeze!` to pass the input to yet
+    /// assert_eq!(scope.enter_with(2, |total| *total), 2);
+    /// assert_eq!(scope.enter_with(3, |total| *total), 5);
+    /// ```
+    pub fn enter_with<'borrow, Output, G>(&'borrow mut self, input: I, f: G) -> Output
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        // SAFETY:
+        // 1. `self.0` is valid as a post-condition of `new`.
+        // 2. The object pointed to by `self.0` did not move and won't before deallocation.
+        // 3. `BoxScope::enter_with` takes an exclusive reference and the reference passed to `f` cannot escape `f`.
+        // 4. `new` only accepts scopes with `I` as input.
+        unsafe { RawScope::enter(self.0, input, f) }.into_entered()
+    }
+
+    /// Attempts to enter the scope, passing `input` to the scope before accessing the data frozen inside of the scope.
+    ///
+    /// See [`BoxScope::try_enter`] and [`BoxScope::enter_with`].
+    ///
+    /// # Errors
+    ///
+    /// - [`ScopeError::Poisoned`] if the scope is poisoned, see [`BoxScope::is_poisoned`].
+    /// - [`ScopeError::ForeignFuture`] if the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    /// - [`ScopeError::NotFilled`] if the underlying future did not fill the value.
+    /// - [`ScopeError::FrozenShared`] if the underlying future froze its data using `freeze_ref!`,
+    ///   see [`BoxScope::enter_shared`].
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    pub fn try_enter_with<'borrow, Output, G>(
+        &'borrow mut self,
+        input: I,
+        f: G,
+    ) -> Result<Output, ScopeError>
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        // SAFETY:
+        // 1. `self.0` is valid as a post-condition of `new`.
+        // 2. The object pointed to by `self.0` did not move and won't before deallocation.
+        // 3. `BoxScope::try_enter_with` takes an exclusive reference and the reference passed to `f` cannot escape `f`.
+        // 4. `new` only accepts scopes with `I` as input.
+        unsafe { RawScope::try_enter(self.0, input, f) }.map(EnterOutcome::into_entered)
+    }
+
+    /// Enters the scope, passing `input` to the scope and blocking the current thread while the underlying future
+    /// awaits other futures.
+    ///
+    /// See [`BoxScope::enter_blocking`] and [`BoxScope::enter_with`].
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    /// - If the scope is poisoned, see [`BoxScope::is_poisoned`].
+    /// - If the underlying future froze its data using `freeze_ref!`, see [`BoxScope::enter_shared`].
+    #[cfg(feature = "std")]
+    pub fn enter_blocking_with<'borrow, Output, G>(&'borrow mut self, input: I, f: G) -> Output
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        // SAFETY:
+        // 1. `self.0` is valid as a post-condition of `new`.
+        // 2. The object pointed to by `self.0` did not move and won't before deallocation.
+        // 3. `BoxScope::enter_blocking_with` takes an exclusive reference and the reference passed to `f` cannot escape `f`.
+        // 4. `new` only accepts scopes with `I` as input.
+        unsafe { RawScope::enter_blocking(self.0, input, f) }.into_entered()
+    }
+}
+
+impl<T, F: ?Sized, Obs, A> BoxScope<T, F, (), Obs, A>
+where
+    T: for<'a> Family<'a>,
+    F: Future,
+    Obs: ScopeObserver,
+    A: ScopeAllocator,
+{
+    /// Enters a scope that can finish, making it possible to access the data frozen inside of the scope.
+    ///
+    /// Returns [`EnterOutcome::Entered`] with the value returned by `f` if the scope froze some data,
+    /// or [`EnterOutcome::Finished`] with a reference to the output of the scope if it finished.
+    /// Once the scope finished, `f` is never called again.
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    /// - If the scope is poisoned, see [`BoxScope::is_poisoned`].
+    /// - If the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    /// - If the underlying future froze its data using `freeze_ref!`, see [`BoxScope::enter_shared`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nolife::{scope, BoxScope, EnterOutcome, SingleFamily};
+    ///
+    /// let mut scope = BoxScope::<SingleFamily<u32>, _>::new(scope!({
+    ///     let mut sum = 0;
+    ///     for mut x in 1..=3 {
+    ///         freeze!(&mut x);
+    ///         sum += x;
+    ///     }
+    ///     sum
+    /// }));
+    ///
+    /// assert_eq!(scope.enter_finite(|x| *x), EnterOutcome::Entered(1));
+    /// assert_eq!(scope.enter_finite(|x| *x), EnterOutcome::Entered(2));
+    /// assert_eq!(scope.enter_finite(|x| *x), EnterOutcome::Entered(3));
+    /// assert_eq!(scope.enter_finite(|x| *x), EnterOutcome::Finished(&mut 6));
+    /// assert_eq!(scope.into_output(), Some(6));
+    /// ```
+    pub fn enter_finite<'borrow, Output, G>(
+        &'borrow mut self,
+        f: G,
+    ) -> EnterOutcome<Output, &'borrow mut F::Output>
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        self.enter_finite_with((), f)
+    }
+
+    /// Resumes the scope up to its next freeze, without accessing the frozen data.
+    ///
+    /// Calling this function is equivalent to entering the scope with a function that does nothing.
+    /// Use [`BoxScope::enter_current`] to access the frozen data afterwards.
+    ///
+    /// # Panics
+    ///
+    /// - If the underlying future panics.
+    /// - If the scope is poisoned, see [`BoxScope::is_poisoned`].
+    /// - If the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nolife::{scope, BoxScope, ResumeOutcome, SingleFamily};
+    ///
+    /// let mut scope = BoxScope::<SingleFamily<u32>, _>::new(scope!({
+    ///     let mut x = 0;
+    ///     for _ in 0..2 {
+    ///         freeze!(&mut x);
+    ///         x += 1;
+    ///     }
+    ///     x
+    /// }));
+    ///
+    /// assert_eq!(scope.resume(), ResumeOutcome::Frozen);
+    /// assert_eq!(scope.enter_current(|x| *x), Ok(0));
+    /// assert_eq!(scope.resume(), ResumeOutcome::Frozen);
+    /// assert_eq!(scope.enter_current(|x| *x), Ok(1));
+    /// assert_eq!(scope.resume(), ResumeOutcome::Finished);
+    /// assert_eq!(scope.into_output(), Some(2));
+    /// ```
+    pub fn resume(&mut self) -> ResumeOutcome {
         // SAFETY:
         // 1. `self.0` is valid as a post-condition of `new`.
         // 2. The object pointed to by `self.0` did not move and won't before deallocation.
-        // 3. `BoxScope::enter` takes an exclusive reference and the reference passed to `f` cannot escape `f`.
-        unsafe { RawScope::enter(self.0, f) }
+        // 3. `BoxScope::resume` takes an exclusive reference.
+        // 4. `BoxScope<T, F>` only holds scopes with `()` as input.
+        match unsafe { RawScope::resume(self.0, ()) } {
+            Ok(outcome) => outcome,
+            // SAFETY: `self.0` is valid as a post-condition of `new`.
+            Err(error) => unsafe { RawScope::fail(self.0, "resume", error) },
+        }
+    }
+
+    /// Resumes the scope `n` times, stopping early if the scope finishes or is frozen forever.
+    ///
+    /// Returns the outcome of the last resume. If `n` is zero, the scope is not resumed and
+    /// [`ResumeOutcome::Frozen`] is returned, unless the scope already finished or is frozen forever.
+    ///
+    /// # Panics
+    ///
+    /// - If the underlying future panics.
+    /// - If the scope is poisoned, see [`BoxScope::is_poisoned`].
+    /// - If the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    pub fn advance_by(&mut self, n: usize) -> ResumeOutcome {
+        // SAFETY: `self.0` is valid as a post-condition of `new`.
+        let mut outcome = unsafe { RawScope::outcome(self.0) };
+        for _ in 0..n {
+            if outcome != ResumeOutcome::Frozen {
+                break;
+            }
+            outcome = self.resume();
+        }
+        outcome
+    }
+}
+
+impl<T, F: ?Sized, I, Obs, A> BoxScope<T, F, I, Obs, A>
+where
+    T: for<'a> Family<'a>,
+    F: Future,
+    Obs: ScopeObserver,
+    A: ScopeAllocator,
+{
+    /// Enters a scope that can finish, passing `input` to the scope before accessing the data frozen inside of the scope.
+    ///
+    /// See [`BoxScope::enter_finite`] and [`BoxScope::enter_with`].
+    ///
+    /// If the scope already finished, `input` is dropped.
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    /// - If the scope is poisoned, see [`BoxScope::is_poisoned`].
+    /// - If the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    /// - If the underlying future froze its data using `freeze_ref!`, see [`BoxScope::enter_shared`].
+    pub fn enter_finite_with<'borrow, Output, G>(
+        &'borrow mut self,
+        input: I,
+        f: G,
+    ) -> EnterOutcome<Output, &'borrow mut F::Output>
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        // SAFETY:
+        // 1. `self.0` is valid as a post-condition of `new`.
+        // 2. The object pointed to by `self.0` did not move and won't before deallocation.
+        // 3. `BoxScope::enter_finite_with` takes an exclusive reference and the reference passed to `f` cannot escape `f`.
+        // 4. `new` only accepts scopes with `I` as input.
+        unsafe { RawScope::enter(self.0, input, f) }
+    }
+
+    /// Enters the scope without resuming it, making it possible to access again the data currently frozen inside of the scope.
+    ///
+    /// Unlike [`BoxScope::enter`], this function does not poll the underlying future, so that the same frozen data
+    /// can be accessed multiple times before the scope advances to its next freeze.
+    ///
+    /// # Errors
+    ///
+    /// - [`ScopeError::Poisoned`] if the scope is poisoned, see [`BoxScope::is_poisoned`].
+    /// - [`ScopeError::NotStarted`] if the scope was never entered, so no data is frozen yet.
+    /// - [`ScopeError::NotFilled`] if the scope finished.
+    /// - [`ScopeError::ForeignFuture`] if the underlying future is waiting for a future other than the [`crate::FrozenFuture`].
+    /// - [`ScopeError::FrozenShared`] if the underlying future froze its data using `freeze_ref!`,
+    ///   see [`BoxScope::enter_shared`].
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nolife::{scope, BoxScope, ScopeError, SingleFamily};
+    ///
+    /// let mut scope = BoxScope::<SingleFamily<u32>>::new_dyn(scope!({
+    ///     let mut step = 0u32;
+    ///     loop {
+    ///         freeze!(&mut step);
+    ///         step += 1;
+    ///     }
+    /// }));
+    ///
+    /// assert_eq!(scope.enter_current(|step| *step), Err(ScopeError::NotStarted));
+    /// assert_eq!(scope.enter(|step| *step), 0);
+    /// // render the same step again
+    /// assert_eq!(scope.enter_current(|step| *step), Ok(0));
+    /// assert_eq!(scope.enter_current(|step| *step), Ok(0));
+    /// // advance to the next step
+    /// assert_eq!(scope.enter(|step| *step), 1);
+    /// ```
+    pub fn enter_current<'borrow, Output, G>(&'borrow mut self, f: G) -> Result<Output, ScopeError>
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        // SAFETY:
+        // 1. `self.0` is valid as a post-condition of `new`.
+        // 2. `BoxScope::enter_current` takes an exclusive reference and the reference passed to `f` cannot escape `f`.
+        unsafe { RawScope::enter_current(self.0, f) }
+    }
+
+    /// Returns the current state of the scope.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nolife::{scope, BoxScope, ScopeState, SingleFamily};
+    ///
+    /// let mut scope = BoxScope::<SingleFamily<u32>>::new_dyn(scope!({
+    ///     freeze!(&mut 0);
+    ///     freeze_forever!(&mut 42)
+    /// }));
+    ///
+    /// assert_eq!(scope.state(), ScopeState::NotStarted);
+    /// scope.enter(|_| ());
+    /// assert_eq!(scope.state(), ScopeState::Frozen);
+    /// scope.enter(|_| ());
+    /// assert_eq!(scope.state(), ScopeState::FrozenForever);
+    /// ```
+    pub fn state(&self) -> ScopeState {
+        // SAFETY: `self.0` is valid as a post-condition of `new`.
+        unsafe { RawScope::state(self.0) }
+    }
+
+    /// Returns the location of the `freeze!` where the scope is currently frozen.
+    ///
+    /// This location is also part of the messages of the panics that occur when entering the scope.
+    ///
+    /// Returns `None` if the scope is not currently frozen, see [`BoxScope::state`].
+    pub fn current_freeze_location(&self) -> Option<&'static Location<'static>> {
+        match self.state() {
+            // SAFETY: `self.0` is valid as a post-condition of `new`.
+            ScopeState::Frozen | ScopeState::FrozenForever => unsafe {
+                RawScope::last_freeze_location(self.0)
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the scope is poisoned.
+    ///
+    /// A scope becomes poisoned when its underlying future panics, or when a function passed to enter the scope panics,
+    /// as the frozen data might then be left in an inconsistent state.
+    /// Entering a poisoned scope panics, and [`BoxScope::try_enter`] returns [`ScopeError::Poisoned`].
+    ///
+    /// Panics in a function accessing a value frozen by `freeze_resumed!` do not poison the scope,
+    /// see [`crate::Resumed::ConsumerPanicked`].
+    pub fn is_poisoned(&self) -> bool {
+        // SAFETY: `self.0` is valid as a post-condition of `new`.
+        unsafe { RawScope::is_poisoned(self.0) }
+    }
+
+    /// Returns the observer of the scope, see [`BoxScope::new_observed`].
+    pub fn observer(&self) -> &Obs {
+        // SAFETY:
+        // 1. `self.0` is valid as a post-condition of `new`.
+        // 2. the observer is only accessed mutably while the scope is entered, which requires `&mut self`.
+        unsafe { RawScope::observer(self.0) }
+    }
+
+    /// Returns the layout of the memory allocated for the scope.
+    ///
+    /// Besides the future of the scope, see [`Scope::FUTURE_LAYOUT`], the allocation holds the state of the scope,
+    /// its output and its observer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nolife::{scope, BoxScope, SingleFamily};
+    ///
+    /// let scope = BoxScope::<SingleFamily<[u8; 1024]>>::new_dyn(scope!({
+    ///     let mut buffer = [0u8; 1024];
+    ///     freeze_forever!(&mut buffer)
+    /// }));
+    /// assert!(scope.allocation_layout().size() > 1024);
+    /// ```
+    pub fn allocation_layout(&self) -> Layout {
+        // SAFETY: `self.0` is valid as a post-condition of `new`.
+        Layout::for_value(unsafe { self.0.as_ref() })
+    }
+
+    /// Returns the allocator of the scope, see [`BoxScope::new_in`].
+    pub fn allocator(&self) -> &A {
+        &self.1
+    }
+
+    /// Replaces the scope with the passed scope, using `coerce` to convert a pointer to the new scope.
+    fn replace_with<S, C>(&mut self, scope: S, coerce: C)
+    where
+        S: Scope<I, Family = T>,
+        S::Future: Future<Output = F::Output>,
+        C: FnOnce(
+            NonNull<RawScope<T, S::Future, F::Output, Obs>>,
+        ) -> NonNull<RawScope<T, F, F::Output, Obs>>,
+    {
+        // SAFETY: `self.0` is valid as a post-condition of `new`.
+        if unsafe { RawScope::fits::<S::Future>(self.0) } {
+            let previous = self.0;
+            // SAFETY:
+            // 1. `previous` is valid as a post-condition of `new`.
+            // 2. The future is only polled once it replaced the previous future.
+            let future = unsafe { RawScope::run_in_place(previous, scope) };
+            // The pointer, including the metadata of an erased future, is updated before the previous future
+            // is dropped, so that it matches the new future even if dropping the previous future panics.
+            self.0 = coerce(previous.cast());
+            // SAFETY:
+            // 1. `previous` is valid as a post-condition of `new`, and no longer used after this call.
+            // 2. `RawScope::fits` returned `true`.
+            // 3. `&mut self` guarantees that no reference to the frozen value or to the output exists.
+            // 4. `future` was created by `RawScope::run_in_place` on `previous`.
+            unsafe { RawScope::replace_future(previous, future) };
+            return;
+        }
+
+        let layout =
+            Layout::new::<RawScope<T, MaybeUninit<S::Future>, F::Output, MaybeUninit<Obs>>>();
+        let raw_scope: *mut RawScope<T, MaybeUninit<S::Future>, F::Output, MaybeUninit<Obs>> = self
+            .1
+            .allocate(layout)
+            .unwrap_or_else(|| alloc::alloc::handle_alloc_error(layout))
+            .cast()
+            .as_ptr();
+        // SAFETY: `raw_scope` was allocated with the layout of a
+        // `RawScope<T, MaybeUninit<S::Future>, F::Output, MaybeUninit<Obs>>`.
+        unsafe {
+            raw_scope.write(
+                RawScope::<T, S::Future, F::Output, MaybeUninit<Obs>>::new_uninit(
+                    MaybeUninit::uninit(),
+                ),
+            )
+        };
+        // guard ensures the new memory is freed on panic (i.e. if scope.run panics)
+        let panic_guard = DeallocGuard {
+            // SAFETY: `raw_scope` allocated by the allocator so is non-null.
+            ptr: unsafe { NonNull::new_unchecked(raw_scope) }.cast(),
+            layout,
+            allocator: &self.1,
+        };
+        let raw_scope: *mut RawScope<T, S::Future, F::Output, MaybeUninit<Obs>> = raw_scope.cast();
+        // SAFETY:
+        // 1. `raw_scope` allocated by the allocator so is valid memory, although the future is not yet initialized
+        // 2. `raw_scope` was created from a valid `RawScope::<T, MaybeUninit<S::Future>, F::Output, MaybeUninit<Obs>>`,
+        //    so `state` and `output` are fully initialized.
+        unsafe { RawScope::open(raw_scope, scope) };
+        mem::forget(panic_guard); // defuse guard
+
+        // SAFETY: `raw_scope` allocated by the allocator so is non-null.
+        let raw_scope = unsafe { NonNull::new_unchecked(raw_scope) };
+        let previous = self.0;
+        // SAFETY: `previous` is valid as a post-condition of `new`.
+        let previous_layout = Layout::for_value(unsafe { previous.as_ref() });
+        // SAFETY:
+        // 1. `previous` is valid as a post-condition of `new`.
+        // 2. `raw_scope` is a valid `RawScope<T, S::Future, F::Output, MaybeUninit<Obs>>`.
+        // 3. `&mut self` guarantees that no reference to the observer exists.
+        unsafe { RawScope::move_observer(previous, raw_scope) };
+        // The observer is initialized, so `raw_scope` is fully initialized.
+        self.0 = coerce(raw_scope.cast());
+
+        // guard ensures the previous memory is freed even if dropping the previous future panics
+        let dealloc_guard = DeallocGuard {
+            ptr: previous.cast(),
+            layout: previous_layout,
+            allocator: &self.1,
+        };
+        // SAFETY:
+        // 1. `previous` is valid, except for its observer that `RawScope::drop_future` does not access.
+        // 2. `&mut self` guarantees that no reference to the frozen value or to the output exists.
+        unsafe { RawScope::drop_future(previous) };
+        drop(dealloc_guard);
+    }
+
+    /// Closes the scope, giving it a chance to clean up before it is dropped.
+    ///
+    /// If the scope is currently frozen by a `freeze_resumed!`, it is resumed one last time,
+    /// and that `freeze_resumed!` evaluates to [`crate::Resumed::Closing`].
+    /// The scope is dropped as soon as it finishes or freezes again.
+    ///
+    /// Otherwise, the scope is dropped without being resumed, like when dropping a [`BoxScope`].
+    ///
+    /// Returns the output of the scope if it finished, either before or while closing.
+    ///
+    /// # Panics
+    ///
+    /// - If the underlying future panics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nolife::{scope, BoxScope, Resumed, SingleFamily};
+    ///
+    /// let mut scope = BoxScope::<SingleFamily<Vec<u32>>, _>::new(scope!({
+    ///     let mut buffer = Vec::new();
+    ///     let mut flushed = 0;
+    ///     loop {
+    ///         match freeze_resumed!(&mut buffer) {
+    ///             Resumed::Normal(()) | Resumed::ConsumerPanicked(()) => {}
+    ///             Resumed::Closing => break,
+    ///         }
+    ///         flushed += buffer.len();
+    ///         buffer.clear();
+    ///     }
+    ///     // flush the remaining data
+    ///     flushed + buffer.len()
+    /// }));
+    ///
+    /// scope.enter_finite(|buffer| buffer.extend([1, 2]));
+    /// scope.enter_finite(|buffer| buffer.push(3));
+    /// assert_eq!(scope.close(), Some(3));
+    /// ```
+    pub fn close(self) -> Option<F::Output> {
+        // SAFETY:
+        // 1. `self.0` is valid as a post-condition of `new`.
+        // 2. The object pointed to by `self.0` did not move and won't before deallocation.
+        // 3. `BoxScope::close` takes ownership of the scope.
+        // 4. `new` only accepts scopes with `I` as input.
+        unsafe { RawScope::close::<I>(self.0) };
+        self.into_output()
+    }
+
+    /// Consumes the scope, returning its output if it finished.
+    ///
+    /// Returns `None` if the scope did not finish yet.
+    pub fn into_output(self) -> Option<F::Output> {
+        // SAFETY:
+        // 1. `self.0` is valid as a post-condition of `new`.
+        // 2. `BoxScope::into_output` takes ownership of the scope.
+        unsafe { RawScope::take_output(self.0) }
+    }
+
+    /// Accesses the data currently frozen inside of the scope through a shared reference.
+    ///
+    /// Unlike [`BoxScope::enter`], this function does not resume the scope: it gives access to the data that was frozen
+    /// by the last call to [`BoxScope::enter`]. Since it only requires a shared reference, the frozen data can be accessed
+    /// multiple times, or from multiple threads at once if the frozen data is [`Sync`].
+    ///
+    /// Data frozen by the `freeze_ref!` macro of [`crate::scope!`] can only be accessed through this function.
+    ///
+    /// Returns `None` if no data is currently frozen, typically because the scope was never entered,
+    /// or if the scope is poisoned, see [`BoxScope::is_poisoned`].
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    pub fn enter_shared<'borrow, Output, G>(&'borrow self, f: G) -> Option<Output>
+    where
+        G: for<'a> FnOnce(&'borrow <T as Family<'a>>::Family) -> Output,
+    {
+        // SAFETY:
+        // 1. `self.0` is valid as a post-condition of `new`.
+        // 2. `BoxScope::enter` takes an exclusive reference, so it cannot be called for `'borrow`.
+        unsafe { RawScope::enter_shared(self.0, f) }
+    }
+}
+
+/// Frees a block of memory when dropped, unless forgotten.
+struct DeallocGuard<'a, A: ScopeAllocator> {
+    ptr: NonNull<u8>,
+    layout: Layout,
+    allocator: &'a A,
+}
+
+impl<A: ScopeAllocator> Drop for DeallocGuard<'_, A> {
+    fn drop(&mut self) {
+        // SAFETY: the guard is only created for blocks of memory allocated by `allocator` with `layout`,
+        // that are no longer used.
+        unsafe { self.allocator.deallocate(self.ptr, self.layout) }
     }
 }
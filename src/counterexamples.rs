@@ -386,3 +386,266 @@
 //!     scope.enter(|x| assert_eq!(*x, 16));
 //! }
 //! ```
+//!
+//! # Dropping the environment borrowed by an erased scope
+//!
+//! ```compile_fail,E0505
+//! use nolife::{scope, BoxScope, Never, SingleFamily, TopScope};
+//! use std::future::Future;
+//!
+//! struct Words<'text> {
+//!     scope: BoxScope<SingleFamily<usize>, dyn Future<Output = Never> + 'text>,
+//! }
+//!
+//! fn count_words<'text>(text: &'text str) -> impl TopScope<Family = SingleFamily<usize>> + 'text {
+//!     scope!({ freeze_forever!(&mut text.split_whitespace().count()) })
+//! }
+//!
+//! fn ref_scope() {
+//!     let text = "Intel the Beagle".to_string();
+//!     let mut words = Words {
+//!         scope: BoxScope::new_dyn(count_words(&text)),
+//!     };
+//!
+//!     drop(text);
+//!
+//!     words.scope.enter(|count| assert_eq!(*count, 3));
+//! }
+//! ```
+//!
+//! # `ArcScope` is not `Send` when the future is not `Send`
+//!
+//! A future holding a non-`Send` value could otherwise be polled and dropped on another thread.
+//!
+//! ```compile_fail,E0277
+//! use nolife::{scope, ArcScope, SingleFamily};
+//! use std::rc::Rc;
+//!
+//! fn non_send_future() {
+//!     let scope = ArcScope::<SingleFamily<u32>, _>::new(scope!({
+//!         let rc = Rc::new(42);
+//!         let mut x = *rc;
+//!         freeze!(&mut x);
+//!         drop(rc);
+//!         freeze_forever!(&mut x)
+//!     }));
+//!
+//!     std::thread::spawn(move || scope.enter(|x| *x));
+//! }
+//! ```
+//!
+//! # `ArcScope` is not `Send` when the frozen data is not `Send`
+//!
+//! The frozen data is accessed by whichever thread enters the scope.
+//!
+//! ```compile_fail,E0277
+//! use nolife::{scope, ArcScope, SingleFamily};
+//! use std::rc::Rc;
+//!
+//! fn non_send_family() {
+//!     let scope = ArcScope::<SingleFamily<Rc<u32>>, _>::new(scope!({
+//!         freeze_forever!(&mut Rc::new(42))
+//!     }));
+//!
+//!     std::thread::spawn(move || scope.enter(|x| **x));
+//! }
+//! ```
+//!
+//! # `ArcScope` is not `Sync` when the future is not `Send`
+//!
+//! Sharing an `ArcScope` allows any thread to poll its future.
+//!
+//! ```compile_fail,E0277
+//! use nolife::{scope, ArcScope, SingleFamily};
+//! use std::rc::Rc;
+//!
+//! fn non_send_shared() {
+//!     let scope = ArcScope::<SingleFamily<u32>, _>::new(scope!({
+//!         let rc = Rc::new(42);
+//!         let mut x = *rc;
+//!         freeze!(&mut x);
+//!         drop(rc);
+//!         freeze_forever!(&mut x)
+//!     }));
+//!
+//!     std::thread::scope(|s| {
+//!         s.spawn(|| scope.enter(|x| *x));
+//!     });
+//! }
+//! ```
+//!
+//! # Frozen data cannot escape a shared `enter`
+//!
+//! Otherwise, two threads (or two calls) could hold a mutable reference to the same frozen data.
+//!
+//! ```compile_fail
+//! use nolife::{scope, ArcScope, SingleFamily};
+//!
+//! fn escaping_shared_enter() {
+//!     let scope = ArcScope::<SingleFamily<u32>, _>::new(scope!({
+//!         freeze_forever!(&mut 42)
+//!     }));
+//!
+//!     let alias1: &mut u32 = scope.enter(|x| x);
+//!     let alias2: &mut u32 = scope.enter(|x| x);
+//!     *alias1 += *alias2;
+//! }
+//! ```
+//!
+//! # `BoxScope` is not `Send` when the future is not `Send`
+//!
+//! ```compile_fail,E0277
+//! use nolife::{scope, BoxScope, SingleFamily};
+//! use std::rc::Rc;
+//!
+//! fn non_send_future() {
+//!     let mut scope = BoxScope::<SingleFamily<u32>, _>::new(scope!({
+//!         let rc = Rc::new(42);
+//!         let mut x = *rc;
+//!         freeze!(&mut x);
+//!         drop(rc);
+//!         freeze_forever!(&mut x)
+//!     }));
+//!
+//!     std::thread::spawn(move || scope.enter(|x| *x));
+//! }
+//! ```
+//!
+//! # `BoxScope` is not `Send` when the frozen data is not `Send`
+//!
+//! ```compile_fail,E0277
+//! use nolife::{scope, BoxScope, SingleFamily};
+//! use std::rc::Rc;
+//!
+//! fn non_send_family() {
+//!     let mut scope = BoxScope::<SingleFamily<Rc<u32>>, _>::new(scope!({
+//!         freeze_forever!(&mut Rc::new(42))
+//!     }));
+//!
+//!     std::thread::spawn(move || scope.enter(|x| **x));
+//! }
+//! ```
+//!
+//! # `BoxScope::new_dyn` erases the `Send`-ness of the future
+//!
+//! Use `BoxScope::new_dyn_send` instead.
+//!
+//! ```compile_fail,E0277
+//! use nolife::{scope, BoxScope, SingleFamily};
+//!
+//! fn erased_non_send() {
+//!     let mut scope = BoxScope::<SingleFamily<u32>>::new_dyn(scope!({
+//!         freeze_forever!(&mut 42)
+//!     }));
+//!
+//!     std::thread::spawn(move || scope.enter(|x| *x));
+//! }
+//! ```
+//!
+//! # `BoxScope::new_dyn_send` requires a `Send` future
+//!
+//! ```compile_fail,E0277
+//! use nolife::{scope, BoxScope, SingleFamily};
+//! use std::rc::Rc;
+//!
+//! fn erased_send_non_send_future() {
+//!     let scope = BoxScope::<SingleFamily<u32>, _>::new_dyn_send(scope!({
+//!         let rc = Rc::new(42);
+//!         let mut x = *rc;
+//!         freeze!(&mut x);
+//!         drop(rc);
+//!         freeze_forever!(&mut x)
+//!     }));
+//! }
+//! ```
+//!
+//! # `BoxScope` is not `Sync` when the frozen data is not `Sync`
+//!
+//! ```compile_fail,E0277
+//! use nolife::{scope, BoxScope, SingleFamily};
+//! use std::cell::Cell;
+//!
+//! fn non_sync_family() {
+//!     let scope = BoxScope::<SingleFamily<Cell<u32>>, _>::new(scope!({
+//!         freeze_forever!(&mut Cell::new(42))
+//!     }));
+//!
+//!     fn assert_sync<T: Sync>(_: &T) {}
+//!     assert_sync(&scope);
+//! }
+//! ```
+//!
+//! # Shared access cannot outlive a resume of the scope
+//!
+//! Resuming the scope could otherwise mutate or drop the frozen data while it is still borrowed.
+//!
+//! ```compile_fail,E0502
+//! use nolife::{scope, BoxScope, SingleFamily};
+//!
+//! fn shared_outliving_resume() {
+//!     let mut scope = BoxScope::<SingleFamily<String>, _>::new(scope!({
+//!         loop {
+//!             let mut s = String::from("Intel the Beagle");
+//!             freeze!(&mut s);
+//!         }
+//!     }));
+//!     scope.enter(|_| ());
+//!
+//!     let s: &String = scope.enter_shared(|s| s).unwrap();
+//!     scope.enter(|_| ());
+//!     println!("{s}");
+//! }
+//! ```
+//!
+//! # Covariant escapes to inner through a shared reference
+//!
+//! ```compile_fail,E0597
+//! use nolife::{scope, BoxScope, Family};
+//! use std::cell::Cell;
+//!
+//! struct Covariant<'a> {
+//!     x: Cell<&'a str>,
+//! }
+//!
+//! struct CovariantFamily;
+//!
+//! impl<'a> Family<'a> for CovariantFamily {
+//!     type Family = Covariant<'a>;
+//! }
+//!
+//! fn covariant_inner_shared() {
+//!     let mut scope = BoxScope::<CovariantFamily, _>::new(scope!({
+//!         let f = Covariant { x: Cell::new("bbb") };
+//!         loop {
+//!             freeze_ref!(&f);
+//!             println!("Called {}", f.x.get())
+//!         }
+//!     }));
+//!     scope.enter_shared(|_| ());
+//!
+//!     {
+//!         let s = String::from("foodog");
+//!         scope.enter_shared(|f| f.x.set(&s));
+//!     }
+//!     scope.enter(|_| ());
+//! }
+//! ```
+//!
+//! # `AsyncBoxScope::enter` is not `Send` when the future of the scope is not `Send`
+//!
+//! ```compile_fail,E0277
+//! use nolife::{scope, AsyncBoxScope, SingleFamily};
+//! use std::rc::Rc;
+//!
+//! fn assert_send<S: Send>(_: S) {}
+//!
+//! fn non_send_async_enter() {
+//!     let mut scope = AsyncBoxScope::<SingleFamily<u32>, _>::new(scope!({
+//!         let rc = Rc::new(42);
+//!         std::future::ready(()).await;
+//!         freeze_forever!(&mut *rc.clone())
+//!     }));
+//!
+//!     assert_send(scope.enter(|x| *x));
+//! }
+//! ```
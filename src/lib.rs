@@ -2,7 +2,19 @@
 #![warn(missing_docs)]
 #![deny(elided_lifetimes_in_paths)]
 #![deny(unsafe_op_in_unsafe_fn)]
-#![doc = include_str!("../README.md")]
+// Without `alloc`, the examples of the README cannot run, as they use `BoxScope`.
+#![cfg_attr(feature = "alloc", doc = include_str!("../README.md"))]
+// Without `std`, the items of the README that require it are linked to their documentation on docs.rs.
+#![cfg_attr(
+    all(feature = "alloc", not(feature = "std")),
+    doc = "[`ArcScope`]: https://docs.rs/nolife/latest/nolife/struct.ArcScope.html\n\
+           [`BoxScope::enter_blocking`]: https://docs.rs/nolife/latest/nolife/struct.BoxScope.html#method.enter_blocking"
+)]
+#![cfg_attr(
+    not(feature = "alloc"),
+    doc = "Open a scope and then freeze it in time for future access.\n\n\
+           Without the `alloc` feature, scopes are stored in place with [`PinnedScope`]."
+)]
 #![doc(
     html_favicon_url = "https://raw.githubusercontent.com/dureuill/nolife/main/assets/nolife-tr.png?raw=true"
 )]
@@ -10,38 +22,211 @@
     html_logo_url = "https://raw.githubusercontent.com/dureuill/nolife/main/assets/nolife-tr.png?raw=true"
 )]
 #![cfg_attr(not(feature = "std"), no_std)]
+#[cfg(feature = "alloc")]
 extern crate alloc;
 
+#[cfg(feature = "alloc")]
+mod allocator;
+#[cfg(feature = "std")]
+mod arc_scope;
+#[cfg(feature = "alloc")]
+mod async_box_scope;
+#[cfg(feature = "alloc")]
 mod box_scope;
-#[cfg(not(miri))]
+#[cfg(all(not(miri), feature = "alloc"))]
 pub mod counterexamples;
+mod observer;
+mod pinned_scope;
+// Without `alloc`, only the functions used by `PinnedScope` are used.
+#[cfg_attr(not(feature = "alloc"), allow(dead_code))]
 mod raw_scope;
+#[cfg(feature = "alloc")]
+mod rc_scope;
 pub mod scope;
+#[cfg(feature = "alloc")]
+mod scope_slab;
+#[cfg(feature = "alloc")]
+mod thin_box_scope;
 #[doc(hidden)]
-pub use raw_scope::{FrozenFuture, TimeCapsule};
+pub use raw_scope::{FrozenForeverFuture, FrozenFuture, ResumedFuture, TimeCapsule};
 /// From <https://blog.aloni.org/posts/a-stack-less-rust-coroutine-100-loc/>, originally from
 /// [genawaiter](https://lib.rs/crates/genawaiter).
 mod waker;
 
+#[cfg(feature = "alloc")]
+pub use allocator::{Global, ScopeAllocator};
+#[cfg(feature = "std")]
+pub use arc_scope::ArcScope;
+#[cfg(feature = "alloc")]
+pub use async_box_scope::{AsyncBoxScope, AsyncEnter};
+#[cfg(feature = "alloc")]
 pub use box_scope::BoxScope;
+pub use observer::{ScopeObserver, ScopeStats};
+pub use pinned_scope::PinnedScope;
+#[cfg(feature = "alloc")]
+pub use rc_scope::{RcScope, WeakRcScope};
 pub use scope::Scope;
 pub use scope::TopScope;
+#[cfg(feature = "alloc")]
+pub use scope_slab::{ScopeHandle, ScopeSlab};
+#[cfg(feature = "alloc")]
+pub use thin_box_scope::ThinBoxScope;
 
 use core::marker::PhantomData;
 
 /// A type for functions that never return.
 ///
 /// Since this enum has no variant, a value of this type can never actually exist.
-/// This type is similar to [`std::convert::Infallible`] and used as a technicality to ensure that
-/// functions passed to [`BoxScope::new_dyn`] never return.
+/// This type is similar to [`core::convert::Infallible`] and used as a technicality to ensure that
+/// functions passed to
+#[cfg_attr(feature = "alloc", doc = "[`BoxScope::new_dyn`]")]
+#[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::new_dyn`")]
+/// never return.
 ///
 /// ## Future compatibility
 ///
 /// Should the [the `!` “never” type][never] ever be stabilized, this type would become a type alias and
-/// eventually be deprecated. See [the relevant section](std::convert::Infallible#future-compatibility)
+/// eventually be deprecated. See [the relevant section](core::convert::Infallible#future-compatibility)
 /// for more information.
 pub enum Never {}
 
+/// The outcome of entering a scope that can finish, see
+#[cfg_attr(feature = "alloc", doc = "[`BoxScope::enter_finite`].")]
+#[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::enter_finite`.")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnterOutcome<R, O> {
+    /// The scope was frozen, and the function passed to enter it returned this value.
+    Entered(R),
+    /// The scope finished with this output, so the function passed to enter it was not called.
+    Finished(O),
+}
+
+/// The outcome of resuming a scope, see
+#[cfg_attr(feature = "alloc", doc = "[`BoxScope::resume`].")]
+#[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::resume`.")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResumeOutcome {
+    /// The scope was frozen, and can be resumed again.
+    Frozen,
+    /// The scope was frozen by `freeze_forever!`, so resuming it again has no effect.
+    FrozenForever,
+    /// The scope finished, so resuming it again has no effect.
+    Finished,
+}
+
+/// The state of a scope, see
+#[cfg_attr(feature = "alloc", doc = "[`BoxScope::state`].")]
+#[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::state`.")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ScopeState {
+    /// The scope was never entered, so its future did not run yet.
+    NotStarted,
+    /// The scope is frozen, and can be resumed.
+    Frozen,
+    /// The scope is frozen by `freeze_forever!`.
+    FrozenForever,
+    /// The scope's future is waiting for a future other than the [`FrozenFuture`], so no data is frozen.
+    Waiting,
+    /// The scope is poisoned, see
+    #[cfg_attr(feature = "alloc", doc = "[`BoxScope::is_poisoned`].")]
+    #[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::is_poisoned`.")]
+    Poisoned,
+    /// The scope finished, see
+    #[cfg_attr(feature = "alloc", doc = "[`BoxScope::enter_finite`].")]
+    #[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::enter_finite`.")]
+    Finished,
+}
+
+/// The value of a `freeze_resumed!` in a [`scope!`], once the scope is resumed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resumed<I> {
+    /// The scope was resumed normally, with this input.
+    Normal(I),
+    /// The function that accessed the frozen value panicked, and the scope was resumed with this input.
+    ///
+    /// The frozen value might have been left in an inconsistent state, that the scope can restore.
+    /// The scope is not poisoned by such a panic.
+    ConsumerPanicked(I),
+    /// The scope is being closed, see
+    #[cfg_attr(feature = "alloc", doc = "[`BoxScope::close`].")]
+    #[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::close`.")]
+    ///
+    /// The scope should clean up and return. It is dropped as soon as it finishes or freezes again.
+    Closing,
+}
+
+impl<R> EnterOutcome<R, &mut Never> {
+    /// A scope whose output is [`Never`] cannot finish.
+    pub(crate) fn into_entered(self) -> R {
+        match self {
+            EnterOutcome::Entered(r) => r,
+            EnterOutcome::Finished(never) => match *never {},
+        }
+    }
+}
+
+/// The error returned when trying to enter a scope that is already entered.
+///
+/// Scopes that can be entered through a shared reference, such as
+#[cfg_attr(feature = "alloc", doc = "[`RcScope`],")]
+#[cfg_attr(not(feature = "alloc"), doc = "`RcScope`,")]
+/// return this error instead of panicking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlreadyEntered;
+
+impl core::fmt::Display for AlreadyEntered {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("the scope is already entered")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AlreadyEntered {}
+
+/// The error returned when a scope cannot be entered, see
+#[cfg_attr(feature = "alloc", doc = "[`BoxScope::try_enter`].")]
+#[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::try_enter`.")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ScopeError {
+    /// The scope is poisoned, because its future or a function accessing its frozen value panicked.
+    ///
+    /// Panics in a function accessing a value frozen by `freeze_resumed!` do not poison the scope,
+    /// see [`Resumed::ConsumerPanicked`].
+    Poisoned,
+    /// The scope's future awaited a future other than the [`FrozenFuture`], and that future is pending.
+    ForeignFuture,
+    /// The scope's future did not fill the value.
+    NotFilled,
+    /// The scope was never entered, so its future did not run yet.
+    NotStarted,
+    /// The scope's future froze its data using `freeze_ref!`, so it can only be accessed through a shared reference,
+    /// see
+    #[cfg_attr(feature = "alloc", doc = "[`BoxScope::enter_shared`].")]
+    #[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::enter_shared`.")]
+    FrozenShared,
+}
+
+impl core::fmt::Display for ScopeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            ScopeError::Poisoned => "the scope is poisoned",
+            ScopeError::ForeignFuture => {
+                "the scope's future awaited a future other than the frozen future"
+            }
+            ScopeError::NotFilled => "the scope's future did not fill the value",
+            ScopeError::NotStarted => "the scope was not started yet",
+            ScopeError::FrozenShared => {
+                "the scope's value was frozen through a shared reference and cannot be accessed mutably"
+            }
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ScopeError {}
+
 /// Describes a family of types containing a lifetime.
 ///
 /// This type is typically implemented on a helper type to describe the lifetime of the borrowed data we want to freeze in time.
@@ -62,7 +247,7 @@ impl<'a, T: 'static> Family<'a> for SingleFamily<T> {
     type Family = T;
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "alloc"))]
 mod test {
     use super::*;
     #[test]
@@ -97,15 +282,50 @@ mod test {
         assert_eq!(scope.enter(|x| *x + 42), 145);
     }
 
+    #[test]
+    #[cfg(feature = "std")]
+    fn send_to_thread() {
+        let mut scope = BoxScope::<SingleFamily<u32>, _>::new(scope!({
+            let mut x = 0u32;
+            loop {
+                freeze!(&mut x);
+                x += 1;
+            }
+        }));
+        scope.enter(|x| assert_eq!(*x, 0));
+
+        let mut scope = std::thread::spawn(move || {
+            scope.enter(|x| assert_eq!(*x, 1));
+            scope
+        })
+        .join()
+        .unwrap();
+
+        scope.enter(|x| assert_eq!(*x, 2));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn send_to_thread_erased() {
+        struct Task {
+            scope: BoxScope<SingleFamily<u32>, dyn core::future::Future<Output = Never> + Send>,
+        }
+        let mut task = Task {
+            scope: BoxScope::new_dyn_send(scope!({ freeze_forever!(&mut 42) })),
+        };
+
+        let x = std::thread::spawn(move || task.scope.enter(|x| *x))
+            .join()
+            .unwrap();
+        assert_eq!(x, 42);
+    }
+
     #[cfg(feature = "std")]
     fn must_panic<F, R>(f: F)
     where
         F: FnOnce() -> R,
     {
-        assert!(matches!(
-            std::panic::catch_unwind(core::panic::AssertUnwindSafe(f)),
-            Err(_)
-        ));
+        assert!(std::panic::catch_unwind(core::panic::AssertUnwindSafe(f)).is_err());
     }
 
     #[test]
@@ -115,7 +335,7 @@ mod test {
             BoxScope::<SingleFamily<u32>, _>::new(unsafe {
                 crate::scope::new_scope(|_time_capsule| {
                     panic!("panicking producer");
-                    #[allow(unreachable_code)]
+                    #[allow(unreachable_code, clippy::empty_loop)]
                     async {
                         loop {}
                     }
@@ -143,9 +363,12 @@ mod test {
         }));
 
         scope.enter(|x| println!("{x}"));
+        assert!(!scope.is_poisoned());
 
         must_panic(|| scope.enter(|x| println!("{x}")));
+        assert!(scope.is_poisoned());
         must_panic(|| scope.enter(|x| println!("{x}")));
+        assert_eq!(scope.try_enter(|x| *x), Err(ScopeError::Poisoned));
     }
 
     #[test]
@@ -163,8 +386,465 @@ mod test {
 
         must_panic(|| scope.enter(|_| panic!()));
 
-        // '1' skipped due to panic
-        scope.enter(|x| assert_eq!(*x, 2));
+        assert!(scope.is_poisoned());
+        must_panic(|| scope.enter(|x| assert_eq!(*x, 1)));
+        assert_eq!(scope.try_enter(|x| *x), Err(ScopeError::Poisoned));
+    }
+
+    #[test]
+    fn enter_current() {
+        let mut scope = BoxScope::<SingleFamily<u32>, _>::new(scope!({
+            let mut x = 0u32;
+            loop {
+                freeze!(&mut x);
+                x += 1;
+            }
+        }));
+
+        assert_eq!(scope.enter_current(|x| *x), Err(ScopeError::NotStarted));
+        assert_eq!(scope.enter(|x| *x), 0);
+        assert_eq!(scope.enter_current(|x| *x), Ok(0));
+        assert_eq!(
+            scope.enter_current(|x| {
+                *x += 42;
+                *x
+            }),
+            Ok(42)
+        );
+        assert_eq!(scope.enter_current(|x| *x), Ok(42));
+        assert_eq!(scope.enter(|x| *x), 43);
+        assert_eq!(scope.enter_current(|x| *x), Ok(43));
+    }
+
+    #[test]
+    fn enter_current_finished() {
+        let mut scope = BoxScope::<SingleFamily<u32>, _>::new(scope!({
+            freeze!(&mut 42);
+        }));
+
+        assert_eq!(scope.enter_finite(|x| *x), EnterOutcome::Entered(42));
+        assert_eq!(scope.enter_current(|x| *x), Ok(42));
+        assert_eq!(scope.enter_finite(|x| *x), EnterOutcome::Finished(&mut ()));
+        assert_eq!(scope.enter_current(|x| *x), Err(ScopeError::NotFilled));
+    }
+
+    #[test]
+    fn resume() {
+        let mut scope = BoxScope::<SingleFamily<u32>, _>::new(scope!({
+            let mut x = 0u32;
+            for _ in 0..3 {
+                freeze!(&mut x);
+                x += 1;
+            }
+            freeze_forever!(&mut x)
+        }));
+
+        assert_eq!(scope.resume(), ResumeOutcome::Frozen);
+        assert_eq!(scope.enter_current(|x| *x), Ok(0));
+        assert_eq!(scope.resume(), ResumeOutcome::Frozen);
+        assert_eq!(scope.enter_current(|x| *x), Ok(1));
+        assert_eq!(scope.advance_by(0), ResumeOutcome::Frozen);
+        assert_eq!(scope.enter_current(|x| *x), Ok(1));
+        assert_eq!(scope.resume(), ResumeOutcome::Frozen);
+        assert_eq!(scope.resume(), ResumeOutcome::FrozenForever);
+        assert_eq!(scope.advance_by(1000), ResumeOutcome::FrozenForever);
+        assert_eq!(scope.enter(|x| *x), 3);
+    }
+
+    #[test]
+    fn advance_by_finished() {
+        let mut scope = BoxScope::<SingleFamily<u32>, _>::new(scope!({
+            let mut x = 0u32;
+            for _ in 0..3 {
+                freeze!(&mut x);
+                x += 1;
+            }
+            x
+        }));
+
+        assert_eq!(scope.advance_by(2), ResumeOutcome::Frozen);
+        assert_eq!(scope.enter_current(|x| *x), Ok(1));
+        assert_eq!(scope.advance_by(usize::MAX), ResumeOutcome::Finished);
+        assert_eq!(scope.resume(), ResumeOutcome::Finished);
+        assert_eq!(scope.into_output(), Some(3));
+    }
+
+    #[test]
+    fn close() {
+        use alloc::rc::Rc;
+        use core::cell::Cell;
+
+        let closed = Rc::new(Cell::new(false));
+        let closed_in_scope = closed.clone();
+        let mut scope = BoxScope::<SingleFamily<u32>>::new_dyn(scope!({
+            let mut x = 0u32;
+            loop {
+                if let Resumed::Closing = freeze_resumed!(&mut x) {
+                    closed_in_scope.set(true);
+                    match freeze_forever!(&mut x) {}
+                }
+                x += 1;
+            }
+        }));
+
+        assert_eq!(scope.enter(|x| *x), 0);
+        assert_eq!(scope.enter(|x| *x), 1);
+        assert!(!closed.get());
+        assert!(scope.close().is_none());
+        assert!(closed.get());
+    }
+
+    #[test]
+    fn close_without_freeze_resumed() {
+        let mut scope = BoxScope::<SingleFamily<u32>, _>::new(scope!({
+            freeze!(&mut 0);
+            freeze_resumed!(&mut 1);
+            2
+        }));
+
+        // not started, nothing to resume
+        assert_eq!(
+            BoxScope::<SingleFamily<u32>, _>::new(scope!({
+                freeze_resumed!(&mut 0);
+                1
+            }))
+            .close(),
+            None
+        );
+
+        scope.enter_finite(|x| assert_eq!(*x, 0));
+        // frozen by `freeze!`, the scope is dropped without resuming
+        assert_eq!(scope.close(), None);
+    }
+
+    #[test]
+    fn close_with_input() {
+        let mut scope = BoxScope::<SingleFamily<u32>, _, u32>::new(scope!(input: u32, {
+            let mut total = 0u32;
+            loop {
+                match freeze_resumed!(&mut total) {
+                    Resumed::Normal(x) | Resumed::ConsumerPanicked(x) => total += x,
+                    Resumed::Closing => return total,
+                }
+            }
+        }));
+
+        scope.enter_finite_with(0, |_| ());
+        scope.enter_finite_with(40, |_| ());
+        scope.enter_finite_with(2, |_| ());
+        assert_eq!(scope.close(), Some(42));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn consumer_panicked() {
+        use alloc::vec::Vec;
+
+        let mut scope = BoxScope::<SingleFamily<Vec<u32>>>::new_dyn(scope!({
+            let mut committed = Vec::new();
+            let mut edited = committed.clone();
+            loop {
+                match freeze_resumed!(&mut edited) {
+                    Resumed::Normal(()) => committed = edited.clone(),
+                    // roll back the failed edit
+                    Resumed::ConsumerPanicked(()) => edited = committed.clone(),
+                    Resumed::Closing => unreachable!(),
+                }
+            }
+        }));
+
+        scope.enter(|v| v.push(1));
+        scope.enter(|v| v.push(2));
+        must_panic(|| {
+            scope.enter(|v| {
+                v.push(3);
+                panic!("failed edit")
+            })
+        });
+        assert!(!scope.is_poisoned());
+        assert_eq!(scope.enter(|v| v.clone()), [1, 2]);
+    }
+
+    #[test]
+    fn freeze_location() {
+        let mut scope = BoxScope::<SingleFamily<u32>>::new_dyn(scope!({
+            freeze!(&mut line!());
+            freeze_resumed!(&mut line!());
+            freeze_forever!(&mut line!())
+        }));
+
+        assert_eq!(scope.current_freeze_location(), None);
+        for _ in 0..4 {
+            let line = scope.enter(|line| *line);
+            let location = scope.current_freeze_location().unwrap();
+            assert_eq!(location.line(), line);
+            assert_eq!(location.file(), file!());
+        }
+        assert_eq!(scope.state(), ScopeState::FrozenForever);
+    }
+
+    #[test]
+    fn scope_state() {
+        let mut scope = BoxScope::<SingleFamily<u32>, _>::new(scope!({
+            freeze!(&mut 0);
+            let () = core::future::pending().await;
+            freeze_forever!(&mut 0)
+        }));
+        assert_eq!(scope.state(), ScopeState::NotStarted);
+        scope.enter(|_| ());
+        assert_eq!(scope.state(), ScopeState::Frozen);
+        assert_eq!(scope.try_enter(|_| ()), Err(ScopeError::ForeignFuture));
+        assert_eq!(scope.state(), ScopeState::Waiting);
+        assert_eq!(scope.current_freeze_location(), None);
+
+        let mut scope = BoxScope::<SingleFamily<u32>, _>::new(scope!({ 42 }));
+        scope.enter_finite(|_| ());
+        assert_eq!(scope.state(), ScopeState::Finished);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn panic_message() {
+        let mut scope = BoxScope::<SingleFamily<u32>>::new_dyn(scope!({
+            freeze!(&mut 0);
+            let () = core::future::pending().await;
+            freeze_forever!(&mut 0)
+        }));
+        scope.enter(|_| ());
+        let line = scope.current_freeze_location().unwrap().line();
+
+        let panic = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| scope.enter(|_| ())))
+            .unwrap_err();
+        let message = panic.downcast_ref::<std::string::String>().unwrap();
+        assert!(message.contains("scope state: Waiting"), "{message}");
+        assert!(
+            message.contains(&std::format!("last frozen at {}:{line}:", file!())),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn enter_shared() {
+        let mut scope = BoxScope::<SingleFamily<u32>, _>::new(scope!({
+            let mut x = 0u32;
+            loop {
+                freeze!(&mut x);
+                x += 1;
+            }
+        }));
+
+        assert_eq!(scope.enter_shared(|x| *x), None);
+        scope.enter(|x| *x += 42);
+        assert_eq!(scope.enter_shared(|x| *x), Some(42));
+        assert_eq!(scope.enter_shared(|x| *x), Some(42));
+        scope.enter(|x| assert_eq!(*x, 43));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn enter_shared_poisoned() {
+        let mut scope = BoxScope::<SingleFamily<u32>, _>::new(scope!({
+            let mut x = 0u32;
+            loop {
+                freeze!(&mut x);
+                x += 1;
+            }
+        }));
+
+        scope.enter(|x| *x += 42);
+        assert_eq!(scope.enter_shared(|x| *x), Some(42));
+        must_panic(|| {
+            scope.enter(|x| {
+                *x = u32::MAX;
+                panic!("broke the invariants of x")
+            })
+        });
+        assert!(scope.is_poisoned());
+        assert_eq!(scope.enter_shared(|x| *x), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn enter_shared_from_threads() {
+        let mut scope =
+            BoxScope::<SingleFamily<u32>, _>::new_dyn_send(scope!({ freeze_forever!(&mut 42) }));
+        scope.enter(|_| ());
+
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| assert_eq!(scope.enter_shared(|x| *x), Some(42)));
+            }
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn freeze_ref() {
+        let mut scope = BoxScope::<SingleFamily<u32>, _>::new(scope!({
+            let x = 42u32;
+            let also_x = &x;
+            freeze_ref!(&x);
+            let mut y = *also_x + 1;
+            freeze_forever!(&mut y)
+        }));
+
+        must_panic(|| scope.enter(|_| ()));
+        assert_eq!(scope.enter_shared(|x| *x), Some(42));
+        scope.enter(|x| assert_eq!(*x, 43));
+        assert_eq!(scope.enter_shared(|x| *x), Some(43));
+    }
+
+    #[test]
+    fn try_enter_freeze_ref() {
+        let mut scope = BoxScope::<SingleFamily<u32>, _>::new(scope!({
+            let x = 42u32;
+            freeze_ref!(&x);
+            let mut y = 43u32;
+            freeze_forever!(&mut y)
+        }));
+
+        assert_eq!(scope.try_enter(|_| ()), Err(ScopeError::FrozenShared));
+        assert!(!scope.is_poisoned());
+        assert_eq!(scope.enter_shared(|x| *x), Some(42));
+        assert_eq!(scope.try_enter(|x| *x), Ok(43));
+        assert_eq!(scope.enter_shared(|x| *x), Some(43));
+    }
+
+    #[test]
+    fn finite_scope() {
+        let mut scope = BoxScope::<SingleFamily<u32>, _>::new(scope!({
+            let mut x = 0u32;
+            freeze!(&mut x);
+            freeze!(&mut x);
+            x
+        }));
+
+        assert_eq!(scope.enter_finite(|x| *x += 21), EnterOutcome::Entered(()));
+        assert_eq!(scope.enter_finite(|x| *x *= 2), EnterOutcome::Entered(()));
+        assert_eq!(
+            scope.enter_finite(|_| unreachable!()),
+            EnterOutcome::<(), _>::Finished(&mut 42)
+        );
+        assert_eq!(
+            scope.enter_finite(|_| unreachable!()),
+            EnterOutcome::<(), _>::Finished(&mut 42)
+        );
+        assert_eq!(scope.enter_shared(|x| *x), None);
+        assert_eq!(scope.into_output(), Some(42));
+    }
+
+    fn parse_scope(
+        s: &'static str,
+    ) -> impl Scope<Family = SingleFamily<u32>, Output = Result<Never, &'static str>> {
+        scope!({
+            let mut x = s.parse::<u32>().map_err(|_| "not a number")?;
+            loop {
+                freeze!(&mut x);
+                x += 1;
+            }
+        })
+    }
+
+    #[test]
+    fn try_new() {
+        let mut scope = BoxScope::try_new(parse_scope("42")).ok().unwrap();
+
+        // the value of the first freeze is not skipped
+        assert_eq!(scope.enter_shared(|x| *x), Some(42));
+        assert!(matches!(
+            scope.enter_finite(|x| *x),
+            EnterOutcome::Entered(42)
+        ));
+        assert!(matches!(
+            scope.enter_finite(|x| *x),
+            EnterOutcome::Entered(43)
+        ));
+    }
+
+    #[test]
+    fn try_new_error() {
+        let scope = BoxScope::try_new(parse_scope("Intel"));
+
+        assert_eq!(scope.err(), Some("not a number"));
+    }
+
+    #[test]
+    fn try_new_error_after_freeze() {
+        let mut scope = BoxScope::<SingleFamily<u32>, _>::try_new(scope!({
+            freeze!(&mut 42);
+            Err("failed after freeze")?;
+            Ok(())
+        }))
+        .ok()
+        .unwrap();
+
+        assert_eq!(scope.enter_finite(|x| *x), EnterOutcome::Entered(42));
+        assert_eq!(
+            scope.enter_finite(|x| *x),
+            EnterOutcome::Finished(&mut Err("failed after freeze"))
+        );
+    }
+
+    #[test]
+    fn unfinished_output() {
+        use alloc::string::String;
+
+        let mut scope = BoxScope::<SingleFamily<u32>, _>::new(scope!({
+            freeze!(&mut 42);
+            String::from("finished")
+        }));
+        scope.enter_finite(|x| assert_eq!(*x, 42));
+
+        assert_eq!(scope.into_output(), None);
+    }
+
+    #[test]
+    fn enter_with_input() {
+        let mut scope = BoxScope::<SingleFamily<u32>, _, u32>::new_dyn(scope!(input: u32, {
+            let mut x = 0u32;
+            loop {
+                x = freeze!(&mut x);
+            }
+        }));
+
+        // not frozen yet, the input is dropped
+        assert_eq!(scope.enter_with(42, |x| *x), 0);
+        assert_eq!(scope.enter_with(1, |x| *x), 1);
+        assert_eq!(scope.enter_with(2, |x| *x), 2);
+    }
+
+    #[test]
+    fn enter_finite_with_input() {
+        use alloc::vec::Vec;
+
+        let mut scope = BoxScope::<SingleFamily<Vec<u32>>, _, Option<u32>>::new(scope!(
+            input: Option<u32>,
+            {
+                let mut v = Vec::new();
+                while let Some(x) = freeze!(&mut v) {
+                    v.push(x);
+                }
+                v
+            }
+        ));
+
+        assert_eq!(
+            scope.enter_finite_with(None, |v| v.len()),
+            EnterOutcome::Entered(0)
+        );
+        assert_eq!(
+            scope.enter_finite_with(Some(1), |v| v.len()),
+            EnterOutcome::Entered(1)
+        );
+        assert_eq!(
+            scope.enter_finite_with(Some(2), |v| v.len()),
+            EnterOutcome::Entered(2)
+        );
+        assert!(matches!(
+            scope.enter_finite_with(None, |_| unreachable!()),
+            EnterOutcome::<(), _>::Finished(_)
+        ));
+        assert_eq!(scope.into_output(), Some(alloc::vec![1, 2]));
     }
 
     #[test]
@@ -207,4 +887,306 @@ mod test {
 
         must_panic(|| scope.enter(|x| assert_eq!(*x, 42)));
     }
+
+    #[test]
+    fn awaiting_in_scope_error() {
+        let mut scope = BoxScope::<SingleFamily<u32>>::new_dyn(scope!({
+            freeze!(&mut 40);
+            let () = core::future::pending().await;
+            freeze_forever!(&mut 42)
+        }));
+
+        assert_eq!(scope.try_enter(|x| *x), Ok(40));
+        assert_eq!(scope.try_enter(|x| *x), Err(ScopeError::ForeignFuture));
+        assert_eq!(scope.try_enter(|x| *x), Err(ScopeError::ForeignFuture));
+        assert!(!scope.is_poisoned());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn awaiting_in_scope_blocks() {
+        use std::{
+            sync::{Arc, Mutex},
+            task::{Poll, Waker},
+        };
+
+        #[derive(Default)]
+        struct Shared {
+            ready: bool,
+            waker: Option<Waker>,
+        }
+
+        struct WaitReady(Arc<Mutex<Shared>>);
+
+        impl core::future::Future for WaitReady {
+            type Output = ();
+
+            fn poll(self: core::pin::Pin<&mut Self>, cx: &mut core::task::Context<'_>) -> Poll<()> {
+                let mut shared = self.0.lock().unwrap();
+                if shared.ready {
+                    return Poll::Ready(());
+                }
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let wait = WaitReady(shared.clone());
+        let mut scope = BoxScope::<SingleFamily<u32>>::new_dyn(scope!({
+            freeze!(&mut 40);
+            wait.await;
+            freeze_forever!(&mut 42)
+        }));
+
+        scope.enter_blocking(|x| assert_eq!(*x, 40));
+
+        let waker_thread = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            let mut shared = shared.lock().unwrap();
+            shared.ready = true;
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        });
+        scope.enter_blocking(|x| assert_eq!(*x, 42));
+        waker_thread.join().unwrap();
+    }
+
+    #[test]
+    fn into_dyn() {
+        fn open<S: TopScope<u32, Family = SingleFamily<u32>>>(
+            scope: S,
+        ) -> BoxScope<SingleFamily<u32>, S::Future, u32, ScopeStats>
+        where
+            S::Future: Send + 'static,
+        {
+            let mut scope = BoxScope::new_observed(scope, ScopeStats::default());
+            assert_eq!(scope.enter_with(0, |x| *x), 0);
+            scope
+        }
+
+        let scope = open(scope!(input: u32, {
+            let mut x = 0u32;
+            loop {
+                x += freeze!(&mut x);
+            }
+        }));
+        let mut scope: BoxScope<
+            SingleFamily<u32>,
+            dyn core::future::Future<Output = Never> + Send,
+            u32,
+            ScopeStats,
+        > = scope.into_dyn_send();
+
+        assert_eq!(scope.enter_with(42, |x| *x), 42);
+        assert_eq!(scope.observer().polls(), 2);
+
+        assert_eq!(scope.enter_with(1, |x| *x), 43);
+    }
+
+    fn counter(
+        start: u32,
+        dropped: &core::cell::Cell<u32>,
+    ) -> impl TopScope<Family = SingleFamily<u32>> + '_ {
+        struct CountOnDrop<'a>(&'a core::cell::Cell<u32>);
+        impl Drop for CountOnDrop<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+        scope!({
+            let _guard = CountOnDrop(dropped);
+            let mut x = start;
+            loop {
+                freeze!(&mut x);
+                x += 1;
+            }
+        })
+    }
+
+    #[test]
+    fn replace() {
+        let dropped = core::cell::Cell::new(0);
+        let mut scope = BoxScope::new_observed(counter(0, &dropped), ScopeStats::default());
+        assert_eq!(scope.enter(|x| *x), 0);
+        assert_eq!(scope.enter(|x| *x), 1);
+        let address = scope.0;
+
+        scope.replace(counter(10, &dropped));
+        assert_eq!(dropped.get(), 1);
+        assert_eq!(scope.0, address);
+        assert_eq!(scope.state(), ScopeState::NotStarted);
+        assert_eq!(scope.enter(|x| *x), 10);
+        assert_eq!(scope.observer().enters(), 3);
+
+        drop(scope);
+        assert_eq!(dropped.get(), 2);
+    }
+
+    #[test]
+    fn replace_dyn() {
+        let dropped = core::cell::Cell::new(0);
+        let mut scope: BoxScope<SingleFamily<u32>, dyn core::future::Future<Output = Never> + '_> =
+            BoxScope::new_dyn(counter(0, &dropped));
+        assert_eq!(scope.enter(|x| *x), 0);
+        let address = scope.0.cast::<u8>();
+
+        // same future type, the allocation is reused
+        scope.replace(counter(10, &dropped));
+        assert_eq!(dropped.get(), 1);
+        assert_eq!(scope.0.cast::<u8>(), address);
+        assert_eq!(scope.enter(|x| *x), 10);
+
+        // another future type, with another layout
+        let large = [1u8; 256];
+        scope.replace(scope!({
+            let mut sum: u32 = large.iter().map(|x| u32::from(*x)).sum();
+            freeze_forever!(&mut sum)
+        }));
+        assert_eq!(dropped.get(), 2);
+        assert_eq!(scope.enter(|x| *x), 256);
+    }
+
+    #[test]
+    fn allocation_layout() {
+        fn future_layout<S: Scope>(_scope: &S) -> core::alloc::Layout {
+            S::FUTURE_LAYOUT
+        }
+
+        let dropped = core::cell::Cell::new(0);
+        let scope = counter(0, &dropped);
+        let future_layout = future_layout(&scope);
+        let scope = BoxScope::new(scope);
+        let layout = scope.allocation_layout();
+        assert!(layout.size() >= future_layout.size());
+        assert!(layout.align() >= future_layout.align());
+
+        let scope: BoxScope<SingleFamily<u32>, dyn core::future::Future<Output = Never> + '_> =
+            scope.into_dyn();
+        assert_eq!(scope.allocation_layout(), layout);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn replace_panicking_drop() {
+        struct PanicOnDrop<'a>(&'a core::cell::Cell<u32>);
+        impl Drop for PanicOnDrop<'_> {
+            fn drop(&mut self) {
+                panic!("dropping the previous future after {} drops", self.0.get())
+            }
+        }
+        struct CountOnDrop<'a>(&'a core::cell::Cell<u32>);
+        impl Drop for CountOnDrop<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let dropped = &core::cell::Cell::new(0);
+        let mut scope: BoxScope<SingleFamily<u32>, dyn core::future::Future<Output = Never> + '_> =
+            BoxScope::new_dyn(scope!({
+                let _guard = PanicOnDrop(dropped);
+                let mut x = 0u32;
+                loop {
+                    freeze!(&mut x);
+                    x += 1;
+                }
+            }));
+        assert_eq!(scope.enter(|x| *x), 0);
+        let address = scope.0.cast::<u8>();
+
+        // a future of another type, with the same layout
+        must_panic(|| {
+            scope.replace(scope!({
+                let _guard = CountOnDrop(dropped);
+                let mut x = 10u32;
+                loop {
+                    freeze!(&mut x);
+                    x += 1;
+                }
+            }))
+        });
+        assert_eq!(scope.0.cast::<u8>(), address);
+        assert_eq!(scope.enter(|x| *x), 10);
+        assert_eq!(scope.enter(|x| *x), 11);
+
+        drop(scope);
+        assert_eq!(dropped.get(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn replace_poisoned() {
+        let dropped = core::cell::Cell::new(0);
+        let mut scope = BoxScope::new(counter(0, &dropped));
+        must_panic(|| scope.enter(|_| panic!()));
+        assert!(scope.is_poisoned());
+
+        scope.replace(counter(10, &dropped));
+        assert!(!scope.is_poisoned());
+        assert_eq!(scope.enter(|x| *x), 10);
+    }
+
+    #[test]
+    fn freeze_forever_not_polled() {
+        let mut scope = BoxScope::<SingleFamily<u32>, _, u32>::new_observed(
+            scope!(input: u32, {
+                let mut x = freeze!(&mut 0);
+                freeze_forever!(&mut x)
+            }),
+            ScopeStats::default(),
+        );
+
+        assert_eq!(scope.enter_with(0, |x| *x), 0);
+        assert_eq!(scope.enter_with(1, |x| *x), 1);
+        for input in 0..100 {
+            assert_eq!(scope.enter_with(input, |x| *x), 1);
+        }
+        assert_eq!(scope.state(), ScopeState::FrozenForever);
+
+        let stats = scope.observer();
+        assert_eq!(stats.polls(), 2);
+        assert_eq!(stats.freezes(), 2);
+        assert_eq!(stats.enters(), 102);
+    }
+
+    /// Compares entering a scope frozen with `freeze_forever!` to entering a scope freezing in a loop.
+    ///
+    /// Run with `cargo test --release -- --ignored --nocapture bench_freeze_forever`.
+    #[test]
+    #[ignore]
+    #[cfg(feature = "std")]
+    fn bench_freeze_forever() {
+        use std::{hint::black_box, time::Instant};
+
+        const ENTERS: u32 = 10_000_000;
+
+        fn bench(name: &str, mut scope: BoxScope<SingleFamily<u32>>) {
+            let start = Instant::now();
+            for _ in 0..ENTERS {
+                black_box(scope.enter(|x| *black_box(x)));
+            }
+            let elapsed = start.elapsed();
+            println!(
+                "{name}: {:?} for {ENTERS} enters ({:.2} ns/enter)",
+                elapsed,
+                elapsed.as_nanos() as f64 / f64::from(ENTERS)
+            );
+        }
+
+        bench(
+            "freeze_forever!",
+            BoxScope::new_dyn(scope!({ freeze_forever!(&mut 42) })),
+        );
+        bench(
+            "loop { freeze! }",
+            BoxScope::new_dyn(scope!({
+                let mut x = 42;
+                loop {
+                    freeze!(&mut x);
+                }
+            })),
+        );
+    }
 }
@@ -0,0 +1,280 @@
+//! Hooks to instrument the lifecycle of a scope, see
+#![cfg_attr(feature = "alloc", doc = "[`crate::BoxScope::new_observed`].")]
+#![cfg_attr(not(feature = "alloc"), doc = "`BoxScope::new_observed`.")]
+
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+/// Observes the lifecycle events of a scope.
+///
+/// An observer is attached to a scope with
+#[cfg_attr(feature = "alloc", doc = "[`crate::BoxScope::new_observed`],")]
+#[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::new_observed`,")]
+/// and is notified each time
+/// the scope is resumed, frozen or entered.
+/// All hooks do nothing by default, and scopes that are not observed use the `()` observer, that costs nothing.
+///
+/// The hooks are called while the scope is entered, and must not access the scope.
+///
+/// # Example
+///
+// Without `alloc`, `BoxScope` is not available to run the example.
+#[cfg_attr(feature = "alloc", doc = "```")]
+#[cfg_attr(not(feature = "alloc"), doc = "```ignore")]
+/// use nolife::{scope, BoxScope, ScopeObserver, SingleFamily};
+///
+/// #[derive(Default)]
+/// struct Resumes(usize);
+///
+/// impl ScopeObserver for Resumes {
+///     fn on_resume(&mut self) {
+///         self.0 += 1;
+///     }
+/// }
+///
+/// let mut scope = BoxScope::<SingleFamily<u32>, _>::new_observed(
+///     scope!({
+///         let mut x = 0u32;
+///         loop {
+///             freeze!(&mut x);
+///             x += 1;
+///         }
+///     }),
+///     Resumes::default(),
+/// );
+/// scope.enter(|x| *x);
+/// scope.enter(|x| *x);
+/// assert_eq!(scope.observer().0, 2);
+/// ```
+pub trait ScopeObserver {
+    /// Called before the future of the scope is polled.
+    fn on_resume(&mut self) {}
+
+    /// Called after the future of the scope was polled, if it froze a value.
+    fn on_freeze(&mut self) {}
+
+    /// Called after the future of the scope was polled, if it finished.
+    fn on_finish(&mut self) {}
+
+    /// Called before the function passed to enter the scope accesses the frozen value.
+    fn on_enter(&mut self) {}
+
+    /// Called after the function passed to enter the scope returned.
+    fn on_exit(&mut self) {}
+
+    /// Called when the future of the scope, or the function passed to enter the scope, panics.
+    ///
+    /// This hook is called while unwinding, so panicking from it aborts the process.
+    fn on_panic(&mut self) {}
+
+    /// Called when the scope is dropped, before its future is dropped.
+    fn on_drop(&mut self) {}
+}
+
+impl ScopeObserver for () {}
+
+/// A [`ScopeObserver`] counting the lifecycle events of a scope.
+///
+/// With the `std` feature, it also measures the time spent in the future of the scope
+/// and in the functions passed to enter the scope.
+///
+/// # Example
+///
+// Without `alloc`, `BoxScope` is not available to run the example.
+#[cfg_attr(feature = "alloc", doc = "```")]
+#[cfg_attr(not(feature = "alloc"), doc = "```ignore")]
+/// use nolife::{scope, BoxScope, ScopeStats, SingleFamily};
+///
+/// let mut scope = BoxScope::<SingleFamily<u32>, _>::new_observed(
+///     scope!({ freeze_forever!(&mut 42) }),
+///     ScopeStats::default(),
+/// );
+/// scope.enter(|x| *x);
+/// scope.enter(|x| *x);
+///
+/// let stats = scope.observer();
+/// assert_eq!(stats.enters(), 2);
+/// assert_eq!(stats.panics(), 0);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ScopeStats {
+    polls: u64,
+    freezes: u64,
+    enters: u64,
+    panics: u64,
+    #[cfg(feature = "std")]
+    body_time: Duration,
+    #[cfg(feature = "std")]
+    consumer_time: Duration,
+    #[cfg(feature = "std")]
+    last_start: Option<Instant>,
+}
+
+impl ScopeStats {
+    /// The number of times the future of the scope was polled.
+    pub fn polls(&self) -> u64 {
+        self.polls
+    }
+
+    /// The number of times the future of the scope froze a value.
+    pub fn freezes(&self) -> u64 {
+        self.freezes
+    }
+
+    /// The number of times a function accessed the frozen value of the scope.
+    pub fn enters(&self) -> u64 {
+        self.enters
+    }
+
+    /// The number of times the future of the scope, or a function passed to enter the scope, panicked.
+    pub fn panics(&self) -> u64 {
+        self.panics
+    }
+
+    /// The total time spent polling the future of the scope, up to a freeze or the end of the future.
+    #[cfg(feature = "std")]
+    pub fn body_time(&self) -> Duration {
+        self.body_time
+    }
+
+    /// The total time spent in the functions passed to enter the scope.
+    #[cfg(feature = "std")]
+    pub fn consumer_time(&self) -> Duration {
+        self.consumer_time
+    }
+
+    /// Starts measuring the time of a poll or of a function passed to enter the scope.
+    fn start(&mut self) {
+        #[cfg(feature = "std")]
+        {
+            self.last_start = Some(Instant::now());
+        }
+    }
+
+    /// The time elapsed since the last call to [`ScopeStats::start`], if any.
+    #[cfg(feature = "std")]
+    fn elapsed(&mut self) -> Duration {
+        self.last_start
+            .take()
+            .map_or(Duration::ZERO, |start| start.elapsed())
+    }
+}
+
+impl ScopeObserver for ScopeStats {
+    fn on_resume(&mut self) {
+        self.polls += 1;
+        self.start();
+    }
+
+    fn on_freeze(&mut self) {
+        self.freezes += 1;
+        #[cfg(feature = "std")]
+        {
+            let elapsed = self.elapsed();
+            self.body_time += elapsed;
+        }
+    }
+
+    fn on_finish(&mut self) {
+        #[cfg(feature = "std")]
+        {
+            let elapsed = self.elapsed();
+            self.body_time += elapsed;
+        }
+    }
+
+    fn on_enter(&mut self) {
+        self.enters += 1;
+        self.start();
+    }
+
+    fn on_exit(&mut self) {
+        #[cfg(feature = "std")]
+        {
+            let elapsed = self.elapsed();
+            self.consumer_time += elapsed;
+        }
+    }
+
+    fn on_panic(&mut self) {
+        self.panics += 1;
+        #[cfg(feature = "std")]
+        {
+            self.last_start = None;
+        }
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod test {
+    use super::*;
+    use crate::{scope, BoxScope, EnterOutcome, SingleFamily};
+    use alloc::rc::Rc;
+    use core::cell::Cell;
+
+    #[test]
+    fn stats() {
+        let mut scope = BoxScope::<SingleFamily<u32>, _>::new_observed(
+            scope!({
+                let mut x = 0u32;
+                freeze!(&mut x);
+                x += 1;
+                freeze!(&mut x);
+                x
+            }),
+            ScopeStats::default(),
+        );
+
+        assert_eq!(scope.enter_finite(|x| *x), EnterOutcome::Entered(0));
+        assert_eq!(scope.enter_finite(|x| *x), EnterOutcome::Entered(1));
+        assert_eq!(scope.enter_finite(|x| *x), EnterOutcome::Finished(&mut 1));
+        assert_eq!(scope.enter_finite(|x| *x), EnterOutcome::Finished(&mut 1));
+
+        let stats = scope.observer();
+        assert_eq!(stats.polls(), 3);
+        assert_eq!(stats.freezes(), 2);
+        assert_eq!(stats.enters(), 2);
+        assert_eq!(stats.panics(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn stats_panic() {
+        let mut scope = BoxScope::<SingleFamily<u32>, _>::new_observed(
+            scope!({ freeze_forever!(&mut 42) }),
+            ScopeStats::default(),
+        );
+
+        assert!(std::panic::catch_unwind(core::panic::AssertUnwindSafe(
+            || scope.enter(|_| panic!())
+        ))
+        .is_err());
+
+        let stats = scope.observer();
+        assert_eq!(stats.enters(), 1);
+        assert_eq!(stats.panics(), 1);
+        assert!(scope.is_poisoned());
+    }
+
+    #[test]
+    fn on_drop() {
+        struct DropObserver(Rc<Cell<bool>>);
+
+        impl ScopeObserver for DropObserver {
+            fn on_drop(&mut self) {
+                self.0.set(true);
+            }
+        }
+
+        let dropped = Rc::new(Cell::new(false));
+        let mut scope = BoxScope::<SingleFamily<u32>, _>::new_observed(
+            scope!({ freeze_forever!(&mut 42) }),
+            DropObserver(dropped.clone()),
+        );
+        scope.enter(|x| assert_eq!(*x, 42));
+        assert!(!dropped.get());
+
+        drop(scope);
+        assert!(dropped.get());
+    }
+}
@@ -0,0 +1,361 @@
+use core::{
+    marker::{PhantomData, PhantomPinned},
+    mem::{ManuallyDrop, MaybeUninit},
+    pin::Pin,
+    ptr::{addr_of_mut, NonNull},
+};
+
+use crate::{
+    raw_scope::RawScope, EnterOutcome, Family, Never, Scope, ScopeError, ScopeState, TopScope,
+};
+
+/// The scope of a [`PinnedScope`], once opened.
+type OpenedRawScope<T, S, I> = RawScope<T, <S as Scope<I>>::Future, <S as Scope<I>>::Output>;
+
+/// A scope stored in place, in pinned storage provided by the caller, that does not allocate.
+///
+/// Unlike the other kinds of scopes, a `PinnedScope` does not require the `alloc` feature.
+/// It can be pinned on the stack with [`core::pin::pin!`], or stored in a `&'static mut` buffer
+/// and pinned with [`Pin::static_mut`].
+///
+/// Creating a `PinnedScope` does not run the scope: it is opened in place the first time it is entered.
+///
+/// `I` is the type of the inputs passed to the scope when resuming it, see [`PinnedScope::enter_with`].
+///
+/// # Example
+///
+/// ```
+/// use core::pin::pin;
+/// use nolife::{scope, PinnedScope, SingleFamily};
+///
+/// let mut scope = pin!(PinnedScope::<SingleFamily<u32>, _>::new(scope!({
+///     let mut x = 0u32;
+///     loop {
+///         freeze!(&mut x);
+///         x += 1;
+///     }
+/// })));
+///
+/// assert_eq!(scope.as_mut().enter(|x| *x), 0);
+/// assert_eq!(scope.as_mut().enter(|x| *x), 1);
+/// ```
+///
+/// With a `&'static mut` buffer:
+///
+/// ```
+/// use core::{mem::MaybeUninit, pin::Pin};
+/// use nolife::{scope, PinnedScope, SingleFamily, TopScope};
+///
+/// fn answer() -> impl TopScope<Family = SingleFamily<u32>> {
+///     scope!({ freeze_forever!(&mut 42) })
+/// }
+///
+/// fn open<S: TopScope<Family = SingleFamily<u32>>>(
+///     buffer: &'static mut MaybeUninit<PinnedScope<SingleFamily<u32>, S>>,
+///     scope: S,
+/// ) -> Pin<&'static mut PinnedScope<SingleFamily<u32>, S>> {
+///     Pin::static_mut(buffer.write(PinnedScope::new(scope)))
+/// }
+///
+/// let buffer = Box::leak(Box::new(MaybeUninit::uninit()));
+/// let mut scope = open(buffer, answer());
+/// assert_eq!(scope.as_mut().enter(|x| *x), 42);
+/// ```
+pub struct PinnedScope<T, S, I = ()>
+where
+    T: for<'a> Family<'a>,
+    S: Scope<I, Family = T>,
+{
+    /// The scope to open, until it is opened.
+    scope: Option<S>,
+    /// `true` once the future of `raw_scope` is initialized.
+    opened: bool,
+    /// The scope, whose future is initialized once `opened` is `true`.
+    raw_scope: ManuallyDrop<RawScope<T, MaybeUninit<S::Future>, S::Output>>,
+    /// The future of the scope can borrow from itself once opened.
+    _pinned: PhantomPinned,
+    marker: PhantomData<fn(I)>,
+}
+
+impl<T, S, I> PinnedScope<T, S, I>
+where
+    T: for<'a> Family<'a>,
+    S: Scope<I, Family = T>,
+{
+    /// Stores the passed scope in place, without running it.
+    ///
+    /// The scope is opened the first time it is entered, once the `PinnedScope` is pinned.
+    pub fn new(scope: S) -> Self {
+        Self {
+            scope: Some(scope),
+            opened: false,
+            raw_scope: ManuallyDrop::new(RawScope::new_uninit(())),
+            _pinned: PhantomPinned,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns a pointer to the scope, opening it if needed.
+    ///
+    /// # Errors
+    ///
+    /// - [`ScopeError::Poisoned`] if the scope panicked while being opened.
+    fn open(self: Pin<&mut Self>) -> Result<NonNull<OpenedRawScope<T, S, I>>, ScopeError> {
+        // SAFETY: the `PinnedScope` is never moved out of the pinned reference.
+        let this = unsafe { self.get_unchecked_mut() };
+        let raw_scope: *mut RawScope<T, S::Future, S::Output> =
+            addr_of_mut!(*this.raw_scope).cast();
+
+        if !this.opened {
+            // If the scope panicked while being opened, its future was not initialized.
+            let scope = this.scope.take().ok_or(ScopeError::Poisoned)?;
+            // SAFETY:
+            // 1. `raw_scope` points to a field of `this`, although the future is not yet initialized
+            // 2. `raw_scope` was created from a valid `RawScope::<T, MaybeUninit<S::Future>, S::Output>`,
+            //    so `state` and `output` are fully initialized.
+            //
+            // Note: as a post-condition of `RawScope`, `raw_scope` is fully initialized.
+            unsafe { RawScope::open(raw_scope, scope) };
+            this.opened = true;
+        }
+
+        // SAFETY: pointers to fields of a reference are non-null.
+        Ok(unsafe { NonNull::new_unchecked(raw_scope) })
+    }
+
+    /// Returns a pointer to the scope if it was opened.
+    fn opened(&self) -> Option<NonNull<OpenedRawScope<T, S, I>>> {
+        self.opened.then(|| NonNull::from(&*self.raw_scope).cast())
+    }
+
+    /// Returns `true` if the scope is poisoned, see
+    #[cfg_attr(feature = "alloc", doc = "[`crate::BoxScope::is_poisoned`].")]
+    #[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::is_poisoned`.")]
+    ///
+    /// A scope that panicked while being opened is also poisoned.
+    pub fn is_poisoned(&self) -> bool {
+        match self.opened() {
+            // SAFETY: the scope is valid as it was opened.
+            Some(raw_scope) => unsafe { RawScope::is_poisoned(raw_scope) },
+            None => self.scope.is_none(),
+        }
+    }
+
+    /// Returns the current state of the scope, see
+    #[cfg_attr(feature = "alloc", doc = "[`crate::BoxScope::state`].")]
+    #[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::state`.")]
+    pub fn state(&self) -> ScopeState {
+        match self.opened() {
+            // SAFETY: the scope is valid as it was opened.
+            Some(raw_scope) => unsafe { RawScope::state(raw_scope) },
+            None if self.scope.is_none() => ScopeState::Poisoned,
+            None => ScopeState::NotStarted,
+        }
+    }
+}
+
+impl<T, S> PinnedScope<T, S>
+where
+    T: for<'a> Family<'a>,
+    S: TopScope<Family = T>,
+{
+    /// Enters the scope, making it possible to access the data frozen inside of the scope.
+    ///
+    /// The first call to this function opens the scope in place.
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    /// - If the scope is poisoned, see [`PinnedScope::is_poisoned`].
+    /// - If the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    /// - If the underlying future froze its data using `freeze_ref!`.
+    pub fn enter<'borrow, Output, G>(self: Pin<&'borrow mut Self>, f: G) -> Output
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        self.enter_with((), f)
+    }
+
+    /// Attempts to enter the scope, making it possible to access the data frozen inside of the scope.
+    ///
+    /// See
+    #[cfg_attr(feature = "alloc", doc = "[`crate::BoxScope::try_enter`].")]
+    #[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::try_enter`.")]
+    ///
+    /// # Errors
+    ///
+    /// - [`ScopeError::Poisoned`] if the scope is poisoned, see [`PinnedScope::is_poisoned`].
+    /// - [`ScopeError::ForeignFuture`] if the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    /// - [`ScopeError::NotFilled`] if the underlying future did not fill the value.
+    /// - [`ScopeError::FrozenShared`] if the underlying future froze its data using `freeze_ref!`.
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    pub fn try_enter<'borrow, Output, G>(
+        self: Pin<&'borrow mut Self>,
+        f: G,
+    ) -> Result<Output, ScopeError>
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        self.try_enter_with((), f)
+    }
+}
+
+impl<T, S, I> PinnedScope<T, S, I>
+where
+    T: for<'a> Family<'a>,
+    S: TopScope<I, Family = T>,
+{
+    /// Enters the scope, passing `input` to the scope before accessing the data frozen inside of the scope.
+    ///
+    /// See
+    #[cfg_attr(feature = "alloc", doc = "[`crate::BoxScope::enter_with`].")]
+    #[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::enter_with`.")]
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    /// - If the scope is poisoned, see [`PinnedScope::is_poisoned`].
+    /// - If the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    /// - If the underlying future froze its data using `freeze_ref!`.
+    pub fn enter_with<'borrow, Output, G>(self: Pin<&'borrow mut Self>, input: I, f: G) -> Output
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        let raw_scope = match self.open() {
+            Ok(raw_scope) => raw_scope,
+            Err(error) => panic!("Cannot enter the scope: {error}"),
+        };
+        // SAFETY:
+        // 1. `raw_scope` was opened by `PinnedScope::open`.
+        // 2. The `PinnedScope` is pinned, so `raw_scope` won't move before it is dropped.
+        // 3. The pinned reference is exclusive for `'borrow`, and the references passed to previous
+        //    calls to `f` cannot outlive the borrow of the scope that created them.
+        // 4. `S` has `I` as input.
+        unsafe { RawScope::<T, S::Future, Never>::enter(raw_scope, input, f) }.into_entered()
+    }
+
+    /// Attempts to enter the scope, passing `input` to the scope before accessing the data frozen inside of the scope.
+    ///
+    /// See [`PinnedScope::try_enter`] and
+    #[cfg_attr(feature = "alloc", doc = "[`crate::BoxScope::enter_with`].")]
+    #[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::enter_with`.")]
+    ///
+    /// # Errors
+    ///
+    /// - [`ScopeError::Poisoned`] if the scope is poisoned, see [`PinnedScope::is_poisoned`].
+    /// - [`ScopeError::ForeignFuture`] if the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    /// - [`ScopeError::NotFilled`] if the underlying future did not fill the value.
+    /// - [`ScopeError::FrozenShared`] if the underlying future froze its data using `freeze_ref!`.
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    pub fn try_enter_with<'borrow, Output, G>(
+        self: Pin<&'borrow mut Self>,
+        input: I,
+        f: G,
+    ) -> Result<Output, ScopeError>
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        let raw_scope = self.open()?;
+        // SAFETY: see `PinnedScope::enter_with`
+        unsafe { RawScope::<T, S::Future, Never>::try_enter(raw_scope, input, f) }
+            .map(EnterOutcome::into_entered)
+    }
+}
+
+impl<T, S, I> Drop for PinnedScope<T, S, I>
+where
+    T: for<'a> Family<'a>,
+    S: Scope<I, Family = T>,
+{
+    fn drop(&mut self) {
+        // The `PinnedScope` is pinned if it was opened, so its future is dropped in place.
+        let raw_scope: *mut RawScope<T, MaybeUninit<S::Future>, S::Output> =
+            addr_of_mut!(*self.raw_scope);
+        if self.opened {
+            // SAFETY: the future was initialized by `PinnedScope::open`.
+            unsafe {
+                core::ptr::drop_in_place(raw_scope.cast::<RawScope<T, S::Future, S::Output>>())
+            }
+        } else {
+            // SAFETY: the future is not initialized, and `MaybeUninit` does not drop it.
+            unsafe { core::ptr::drop_in_place(raw_scope) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{scope, SingleFamily};
+    use core::{cell::Cell, pin::pin};
+
+    #[test]
+    fn enter_with_input() {
+        let mut scope = pin!(PinnedScope::<SingleFamily<u32>, _, u32>::new(
+            scope!(input: u32, {
+                let mut x = 0u32;
+                loop {
+                    x += freeze!(&mut x);
+                }
+            })
+        ));
+
+        assert_eq!(scope.state(), ScopeState::NotStarted);
+        assert_eq!(scope.as_mut().enter_with(1, |x| *x), 0);
+        assert_eq!(scope.as_mut().enter_with(2, |x| *x), 2);
+        assert_eq!(scope.as_mut().try_enter_with(3, |x| *x), Ok(5));
+        assert_eq!(scope.state(), ScopeState::Frozen);
+    }
+
+    #[test]
+    fn lazy_open() {
+        let opened = &Cell::new(false);
+        let dropped = &Cell::new(false);
+        struct SetOnDrop<'a>(&'a Cell<bool>);
+        impl Drop for SetOnDrop<'_> {
+            fn drop(&mut self) {
+                self.0.set(true)
+            }
+        }
+
+        {
+            let scope = pin!(PinnedScope::<SingleFamily<u32>, _>::new(scope!({
+                opened.set(true);
+                let _guard = SetOnDrop(dropped);
+                freeze_forever!(&mut 42)
+            })));
+            assert!(!opened.get());
+            assert_eq!(scope.try_enter(|x| *x), Ok(42));
+            assert!(opened.get());
+            assert!(!dropped.get());
+        }
+        assert!(dropped.get());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn poisoned_after_panic() {
+        let mut scope = pin!(PinnedScope::<SingleFamily<u32>, _>::new(scope!({
+            freeze_forever!(&mut 42)
+        })));
+
+        assert!(
+            std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| scope
+                .as_mut()
+                .enter(|_| panic!())))
+            .is_err()
+        );
+        assert!(scope.is_poisoned());
+        assert_eq!(scope.as_mut().try_enter(|x| *x), Err(ScopeError::Poisoned));
+    }
+}
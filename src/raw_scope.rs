@@ -1,15 +1,22 @@
-use crate::{waker, Family, Never, TopScope};
+use crate::{
+    observer::ScopeObserver, scope::Scope, waker, EnterOutcome, Family, Never, ResumeOutcome,
+    Resumed, ScopeError, ScopeState,
+};
 use core::{
+    alloc::Layout,
     future::Future,
     marker::PhantomData,
     mem::MaybeUninit,
+    panic::Location,
     pin::Pin,
     ptr::{addr_of_mut, NonNull},
     task::Poll,
 };
 
 /// The future resulting from using a time capsule to freeze some scope.
-pub struct FrozenFuture<'a, 'b, T>
+///
+/// It resolves to the input passed to the scope when resuming it.
+pub struct FrozenFuture<'a, 'b, T, I = ()>
 where
     T: for<'c> Family<'c>,
     'b: 'a,
@@ -19,20 +26,62 @@ where
     // operations that "touch" the FrozenFuture such moving it or passing it to a function.
     // (This probably wasn't exploitable with the scope! macro, but it still seems
     // more correct this way.)
-    mut_ref: State<T>,
+    frozen: Frozen<T>,
+    shared: bool,
+    forever: bool,
+    closable: bool,
+    location: &'static Location<'static>,
     state: *mut State<T>,
     marker: PhantomData<&'a mut <T as Family<'b>>::Family>,
+    input: PhantomData<fn() -> I>,
+}
+
+/// The future resulting from using a time capsule to freeze some scope, that can be resumed to close the scope.
+///
+/// It resolves to [`Resumed::Normal`] with the input passed to the scope when resuming it,
+/// to [`Resumed::ConsumerPanicked`] if the function that accessed the frozen value panicked,
+/// or to [`Resumed::Closing`] when the scope is closed, see
+#[cfg_attr(feature = "alloc", doc = "[`crate::BoxScope::close`].")]
+#[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::close`.")]
+pub struct ResumedFuture<'a, 'b, T, I = ()>(FrozenFuture<'a, 'b, T, I>)
+where
+    T: for<'c> Family<'c>,
+    'b: 'a;
+
+/// The future resulting from using a time capsule to freeze some scope forever.
+///
+/// It never resolves: once it froze its value, the scope is not polled anymore and entering it reuses the frozen value.
+/// Should it be polled again anyway, it freezes the same value again.
+pub struct FrozenForeverFuture<'a, 'b, T, I = ()>
+where
+    T: for<'c> Family<'c>,
+    'b: 'a,
+{
+    future: FrozenFuture<'a, 'b, T, I>,
+    value: Frozen<T>,
 }
 
 /// Passed to the closures of a scope so that they can freeze the scope.
-pub struct TimeCapsule<T>
+///
+/// `I` is the type of the inputs passed to the scope when resuming it.
+pub struct TimeCapsule<T, I = ()>
 where
     T: for<'a> Family<'a>,
 {
     pub(crate) state: *mut State<T>,
+    pub(crate) marker: PhantomData<fn(I)>,
 }
 
-impl<T> Clone for TimeCapsule<T>
+// SAFETY: a `TimeCapsule` is only reachable from the future of its scope, that is where the frozen reference lives.
+// Sending it along with that future is sound if the frozen data itself can be sent to another thread.
+unsafe impl<T, I> Send for TimeCapsule<T, I>
+where
+    T: for<'a> Family<'a>,
+    for<'a> <T as Family<'a>>::Family: Send,
+{
+}
+
+impl<T, I> Clone for TimeCapsule<T, I>
 where
     T: for<'a> Family<'a>,
 {
@@ -41,127 +90,316 @@ where
     }
 }
 
-impl<T> Copy for TimeCapsule<T> where T: for<'a> Family<'a> {}
+impl<T, I> Copy for TimeCapsule<T, I> where T: for<'a> Family<'a> {}
 
-impl<T> TimeCapsule<T>
+impl<T, I> TimeCapsule<T, I>
 where
     T: for<'a> Family<'a>,
 {
     /// Freeze a scope, making the data it has borrowed available to the outside.
     ///
     /// Once a scope is frozen, its borrowed data can be accessed through [`crate::BoxScope::enter`].
+    /// The returned future resolves to the input passed when resuming the scope, see [`crate::BoxScope::enter_with`].
     ///
     /// For simple cases where you don't need to execute code in the scope between two calls to `enter`,
     /// use [`Self::freeze_forever`].
+    #[track_caller]
     pub fn freeze<'a, 'b>(
         &'a mut self,
         t: &'a mut <T as Family<'b>>::Family,
-    ) -> FrozenFuture<'a, 'b, T>
+    ) -> FrozenFuture<'a, 'b, T, I>
+    where
+        'b: 'a,
+    {
+        FrozenFuture {
+            frozen: Some(NonNull::from(t).cast()),
+            shared: false,
+            forever: false,
+            closable: false,
+            location: Location::caller(),
+            state: self.state,
+            marker: PhantomData,
+            input: PhantomData,
+        }
+    }
+
+    /// Freeze a scope, making the data it has borrowed available to the outside through a shared reference.
+    ///
+    /// Once a scope is frozen this way, its borrowed data can be accessed through [`crate::BoxScope::enter_shared`],
+    /// but not through [`crate::BoxScope::enter`].
+    ///
+    /// Unlike [`Self::freeze`], this allows the scope to keep other shared references to the frozen data.
+    #[track_caller]
+    pub fn freeze_ref<'a, 'b>(
+        &'a mut self,
+        t: &'a <T as Family<'b>>::Family,
+    ) -> FrozenFuture<'a, 'b, T, I>
     where
         'b: 'a,
     {
         FrozenFuture {
-            mut_ref: Some(NonNull::from(t).cast()),
+            frozen: Some(NonNull::from(t).cast()),
+            shared: true,
+            forever: false,
+            closable: false,
+            location: Location::caller(),
             state: self.state,
             marker: PhantomData,
+            input: PhantomData,
         }
     }
 
+    /// Freeze a scope, making the data it has borrowed available to the outside, and allowing to close the scope
+    /// while it is frozen.
+    ///
+    /// Like [`Self::freeze`], but the returned future resolves to [`Resumed::Closing`] when the scope is closed
+    /// with [`crate::BoxScope::close`], so that the scope can clean up before it is dropped.
+    ///
+    /// If the function accessing the frozen value panics, the scope is not poisoned: instead,
+    /// the returned future resolves to [`Resumed::ConsumerPanicked`] on the next resume,
+    /// so that the scope can restore the invariants of the frozen value.
+    #[track_caller]
+    pub fn freeze_resumed<'a, 'b>(
+        &'a mut self,
+        t: &'a mut <T as Family<'b>>::Family,
+    ) -> ResumedFuture<'a, 'b, T, I>
+    where
+        'b: 'a,
+    {
+        ResumedFuture(FrozenFuture {
+            frozen: Some(NonNull::from(t).cast()),
+            shared: false,
+            forever: false,
+            closable: true,
+            location: Location::caller(),
+            state: self.state,
+            marker: PhantomData,
+            input: PhantomData,
+        })
+    }
+
     /// Freeze a scope forever, making the data it has borrowed available to the outside.
     ///
     /// Once a scope is frozen, its borrowed data can be accessed through [`crate::BoxScope::enter`].
     ///
     /// If you need to execute code between two calls to [`crate::BoxScope::enter`], use [`Self::freeze`].
-    pub async fn freeze_forever<'a, 'b>(
+    ///
+    /// The inputs passed when resuming the scope are dropped.
+    #[track_caller]
+    pub fn freeze_forever<'a, 'b>(
         &'a mut self,
         t: &'a mut <T as Family<'b>>::Family,
-    ) -> Never {
-        loop {
-            self.freeze(t).await
+    ) -> FrozenForeverFuture<'a, 'b, T, I>
+    where
+        'b: 'a,
+    {
+        let value = Some(NonNull::from(t).cast());
+        FrozenForeverFuture {
+            future: FrozenFuture {
+                frozen: value,
+                shared: false,
+                forever: true,
+                closable: false,
+                location: Location::caller(),
+                state: self.state,
+                marker: PhantomData,
+                input: PhantomData,
+            },
+            value,
         }
     }
 }
 
 // This type is a pointer-type and lifetime-erased equivalent of
-// Option<&'a mut <T as Family<'b>>::Family>.
+// Option<&'a mut <T as Family<'b>>::Family> (or Option<&'a <T as Family<'b>>::Family>).
 //
 // NonNull differs in variance, which would typically be corrected
 // with a `PhantomData` marker, however a projection like
 // `<T as Family<'static>>::Family>` has T invariant already anyway.
-pub(crate) type State<T> = Option<NonNull<<T as Family<'static>>::Family>>;
+pub(crate) type Frozen<T> = Option<NonNull<<T as Family<'static>>::Family>>;
+
+/// State shared between a scope and its frozen futures.
+pub(crate) struct State<T>
+where
+    T: for<'a> Family<'a>,
+{
+    /// The value currently frozen, `None` if the scope is not frozen.
+    frozen: Frozen<T>,
+    /// `true` if `frozen` was frozen from a shared reference, and must not be accessed mutably.
+    shared: bool,
+    /// `true` if `frozen` was frozen by [`TimeCapsule::freeze_forever`], so that [`RawScope::poll`] does not
+    /// poll the future of the scope anymore and reuses the frozen value.
+    forever: bool,
+    /// `true` if `frozen` was frozen by [`TimeCapsule::freeze_resumed`], so that the scope can be closed.
+    closable: bool,
+    /// `true` if the scope is being closed, so that a [`ResumedFuture`] resolves to [`Resumed::Closing`].
+    closing: bool,
+    /// `true` if a function accessing the value frozen by [`TimeCapsule::freeze_resumed`] panicked,
+    /// so that the [`ResumedFuture`] resolves to [`Resumed::ConsumerPanicked`].
+    consumer_panicked: bool,
+    /// Type-erased pointer to the `Option<I>` input of the scope while it is resumed, null otherwise.
+    ///
+    /// `I` is the input type of the `TimeCapsule<T, I>` that was passed to the scope.
+    input: *mut (),
+    /// `true` if the future of the scope or a function accessing its frozen value panicked.
+    poisoned: bool,
+    /// `true` if the future of the scope was polled at least once.
+    started: bool,
+    /// The location of the last freeze of the scope.
+    location: Option<&'static Location<'static>>,
+    /// `true` if the future of the scope was polled up to its first freeze before the scope was entered,
+    /// so that the next call to [`RawScope::poll`] must not resume it.
+    primed: bool,
+}
+
+impl<T> State<T>
+where
+    T: for<'a> Family<'a>,
+{
+    const fn new() -> Self {
+        Self {
+            frozen: None,
+            shared: false,
+            forever: false,
+            closable: false,
+            closing: false,
+            consumer_panicked: false,
+            input: core::ptr::null_mut(),
+            poisoned: false,
+            started: false,
+            location: None,
+            primed: false,
+        }
+    }
+}
+
+/// Poisons the state of a scope and notifies its observer when dropped, unless forgotten.
+///
+/// Used to poison a scope when a panic occurs.
+struct PoisonGuard<T: for<'a> Family<'a>, Obs: ScopeObserver> {
+    state: *mut State<T>,
+    observer: *mut Obs,
+}
+
+impl<T: for<'a> Family<'a>, Obs: ScopeObserver> Drop for PoisonGuard<T, Obs> {
+    fn drop(&mut self) {
+        // SAFETY: the state and the observer outlive the guard
+        unsafe {
+            (*self.state).poisoned = true;
+            (*self.observer).on_panic();
+        }
+    }
+}
+
+/// The state of a scope and the location of its last freeze, displayed in panic messages.
+struct Diagnostics {
+    state: ScopeState,
+    location: Option<&'static Location<'static>>,
+}
+
+impl core::fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "scope state: {:?}", self.state)?;
+        if let Some(location) = self.location {
+            write!(f, ", last frozen at {location}")?;
+        }
+        Ok(())
+    }
+}
 
 /// Underlying representation of a scope.
-// SAFETY: repr C to ensure conversion between RawScope<T, MaybeUninit<F>> and RawScope<T, F>
+///
+/// `O` is the output type of the scope's future. For top-level scopes, `O` is [`Never`]
+/// so that `output` does not take any space.
+///
+/// `Obs` is the observer of the scope. For scopes that are not observed, `Obs` is `()`
+/// so that `observer` does not take any space and its hooks compile to nothing.
+// SAFETY: repr C to ensure conversion between RawScope<T, MaybeUninit<F>, O, Obs> and RawScope<T, F, O, Obs>
 // does not rely on unstable memory layout.
 #[repr(C)]
-pub(crate) struct RawScope<T, F: ?Sized>
+pub(crate) struct RawScope<T, F: ?Sized, O = Never, Obs = ()>
 where
     T: for<'a> Family<'a>,
 {
     state: State<T>,
+    output: Option<O>,
+    observer: Obs,
     active_fut: F,
 }
 
-impl<T, F> RawScope<T, F>
+impl<T, F, O, Obs> RawScope<T, F, O, Obs>
 where
     T: for<'a> Family<'a>,
 {
-    /// Creates a new closed scope.
-    pub fn new_uninit() -> RawScope<T, MaybeUninit<F>> {
+    /// Creates a new closed scope, observed by `observer`.
+    pub fn new_uninit(observer: Obs) -> RawScope<T, MaybeUninit<F>, O, Obs> {
         RawScope {
-            state: None,
+            state: State::new(),
+            output: None,
+            observer,
             active_fut: MaybeUninit::uninit(),
         }
     }
 }
 
-struct RawScopeFields<T, F: ?Sized>
+struct RawScopeFields<T, F: ?Sized, O, Obs>
 where
     T: for<'a> Family<'a>,
 {
     state: *mut State<T>,
+    output: *mut Option<O>,
+    observer: *mut Obs,
     active_fut: *mut F,
 }
-impl<T, F: ?Sized> RawScope<T, F>
+impl<T, F: ?Sized, O, Obs> RawScope<T, F, O, Obs>
 where
     T: for<'a> Family<'a>,
 {
     /// SAFETY:
     ///
-    /// 1. `this` points to an allocation that can hold a `RawScope<T, F>`,
+    /// 1. `this` points to an allocation that can hold a `RawScope<T, F, O, Obs>`,
     ///    not necessarily initialized or properly aligned.
-    unsafe fn fields(this: *mut Self) -> RawScopeFields<T, F> {
+    unsafe fn fields(this: *mut Self) -> RawScopeFields<T, F, O, Obs> {
         RawScopeFields {
             // SAFETY: precondition (1)
             state: unsafe { addr_of_mut!((*this).state) },
             // SAFETY: precondition (1)
+            output: unsafe { addr_of_mut!((*this).output) },
+            // SAFETY: precondition (1)
+            observer: unsafe { addr_of_mut!((*this).observer) },
+            // SAFETY: precondition (1)
             active_fut: unsafe { addr_of_mut!((*this).active_fut) },
         }
     }
 }
 
-impl<T, F> RawScope<T, F>
+impl<T, F, O, Obs> RawScope<T, F, O, Obs>
 where
     T: for<'a> Family<'a>,
-    F: Future<Output = Never>,
+    F: Future<Output = O>,
 {
     /// # Safety
     ///
-    /// 1. `this` points to a properly aligned allocation that can hold a `RawScope<T, F>`, where `active_fut` is not necessarily initialized.
-    /// 2. `this.state` is initialized.
+    /// 1. `this` points to a properly aligned allocation that can hold a `RawScope<T, F, O>`, where `active_fut` is not necessarily initialized.
+    /// 2. `this.state` and `this.output` are initialized.
     ///
     /// # Post-condition
     ///
     /// 1. `this.active_fut` is fully initialized
-    pub(crate) unsafe fn open<S: TopScope<Family = T, Future = F>>(this: *mut Self, scope: S)
+    pub(crate) unsafe fn open<I, S: Scope<I, Family = T, Future = F>>(this: *mut Self, scope: S)
     where
         T: for<'a> Family<'a>,
-        F: Future<Output = Never>,
+        F: Future<Output = O>,
     {
         // SAFETY: precondition (1)
-        let RawScopeFields { state, active_fut } = unsafe { Self::fields(this) };
+        let RawScopeFields {
+            state, active_fut, ..
+        } = unsafe { Self::fields(this) };
 
-        let time_capsule = TimeCapsule { state };
+        let time_capsule = TimeCapsule {
+            state,
+            marker: PhantomData,
+        };
 
         // SAFETY:
         // - precondition (1)
@@ -172,54 +410,810 @@ where
     }
 }
 
-impl<T, F: ?Sized> RawScope<T, F>
+impl<T, F: ?Sized, O, Obs> RawScope<T, F, O, Obs>
 where
     T: for<'a> Family<'a>,
-    F: Future<Output = Never>,
+    F: Future<Output = O>,
 {
+    /// Returns `true` if the allocation of `this` can hold a `RawScope<T, G, O, Obs>` instead,
+    /// with its future at the same place.
+    ///
     /// # Safety
     ///
-    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F>`.
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O, Obs>`.
+    pub(crate) unsafe fn fits<G: Future<Output = O>>(this: NonNull<Self>) -> bool {
+        let other: NonNull<RawScope<T, G, O, Obs>> = this.cast();
+        // SAFETY: precondition (1)
+        Layout::for_value(unsafe { this.as_ref() }) == Layout::new::<RawScope<T, G, O, Obs>>()
+            // SAFETY: precondition (1), and `other` has the same layout as `this`.
+            && unsafe { Self::fields(this.as_ptr()).active_fut.cast::<u8>() }
+                == unsafe { RawScope::fields(other.as_ptr()).active_fut.cast::<u8>() }
+    }
+
+    /// Drops the future and the output of the scope in place, and resets its state.
+    ///
+    /// The observer of the scope is neither dropped nor notified.
+    ///
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O, Obs>`.
+    /// 2. No reference to the frozen value or to the output.
+    ///
+    /// # Post-condition
+    ///
+    /// 1. `this.state` and `this.output` are initialized, `this.active_fut` is not, even if this function panics.
+    pub(crate) unsafe fn drop_future(this: NonNull<Self>) {
+        // SAFETY: precondition (1)
+        let RawScopeFields {
+            state,
+            output,
+            active_fut,
+            ..
+        } = unsafe { Self::fields(this.as_ptr()) };
+
+        /// Resets the state when dropped, so that it is reset even if dropping the future panics.
+        struct ResetGuard<T: for<'a> Family<'a>>(*mut State<T>);
+        impl<T: for<'a> Family<'a>> Drop for ResetGuard<T> {
+            fn drop(&mut self) {
+                // SAFETY: `State` has no drop glue, and the state outlives the guard.
+                unsafe { self.0.write(State::new()) }
+            }
+        }
+
+        // SAFETY: precondition (1). The output is not pinned, so it can be moved out to be dropped last.
+        let output = unsafe { output.replace(None) };
+        let reset_guard = ResetGuard(state);
+        // SAFETY: precondition (1) and (2)
+        unsafe { core::ptr::drop_in_place(active_fut) };
+        drop(reset_guard);
+        drop(output);
+    }
+
+    /// Moves the observer of the scope to `to`.
+    ///
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O, Obs>`.
+    /// 2. `to` points to a properly aligned `RawScope<T, G, O, MaybeUninit<Obs>>`.
+    /// 3. No reference to the observer.
+    ///
+    /// # Post-condition
+    ///
+    /// 1. The observer of `this` is moved out, and must not be used or dropped.
+    pub(crate) unsafe fn move_observer<G: ?Sized>(
+        this: NonNull<Self>,
+        to: NonNull<RawScope<T, G, O, MaybeUninit<Obs>>>,
+    ) {
+        // SAFETY: precondition (1)
+        let RawScopeFields { observer, .. } = unsafe { Self::fields(this.as_ptr()) };
+        // SAFETY: precondition (2)
+        let RawScopeFields { observer: to, .. } = unsafe { RawScope::fields(to.as_ptr()) };
+        // SAFETY: precondition (1), (2) and (3)
+        unsafe { core::ptr::copy_nonoverlapping(observer, to.cast::<Obs>(), 1) }
+    }
+
+    /// Runs `scope` with the state of this scope, creating a future that can replace the future of this scope
+    /// with [`RawScope::replace_future`].
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics. The scope is left unchanged.
+    ///
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O, Obs>`.
+    /// 2. The returned future is only polled once it replaced the future of this scope.
+    pub(crate) unsafe fn run_in_place<I, S: Scope<I, Family = T>>(
+        this: NonNull<Self>,
+        scope: S,
+    ) -> S::Future {
+        // SAFETY: precondition (1)
+        let RawScopeFields { state, .. } = unsafe { Self::fields(this.as_ptr()) };
+
+        let time_capsule = TimeCapsule {
+            state,
+            marker: PhantomData,
+        };
+        // SAFETY:
+        // - using `scope.run` from the executor, precondition (2)
+        // - the future does not access the state before being polled,
+        //   so it can be created before the previous future is dropped.
+        unsafe { scope.run(time_capsule) }
+    }
+
+    /// Replaces the future of the scope with `future`, dropping the previous future in place.
+    ///
+    /// The observer of the scope is kept.
+    ///
+    /// # Panics
+    ///
+    /// - If dropping the previous future or output panics. The future is replaced regardless.
+    ///
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O, Obs>`.
+    /// 2. [`RawScope::fits`] returns `true` for `G`.
+    /// 3. No reference to the frozen value or to the output.
+    /// 4. `future` was created by [`RawScope::run_in_place`] on `this`.
+    ///
+    /// # Post-condition
+    ///
+    /// 1. `this` cast to a `RawScope<T, G, O, Obs>` is fully initialized, even if this function panics.
+    ///    Callers must stop using `this` as a `RawScope<T, F, O, Obs>` before calling this function,
+    ///    as it would not be valid anymore when unwinding.
+    pub(crate) unsafe fn replace_future<G: Future<Output = O>>(this: NonNull<Self>, future: G) {
+        let other: NonNull<RawScope<T, G, O, Obs>> = this.cast();
+        // SAFETY: precondition (1) and (2)
+        let RawScopeFields { active_fut, .. } = unsafe { RawScope::fields(other.as_ptr()) };
+
+        /// Writes the future when dropped, so that it is written even if dropping the previous future panics.
+        struct WriteGuard<G> {
+            active_fut: *mut G,
+            future: core::mem::ManuallyDrop<G>,
+        }
+        impl<G> Drop for WriteGuard<G> {
+            fn drop(&mut self) {
+                // SAFETY: the future is only taken here, and the previous future was dropped.
+                unsafe {
+                    self.active_fut
+                        .write(core::mem::ManuallyDrop::take(&mut self.future))
+                }
+            }
+        }
+
+        let write_guard = WriteGuard {
+            active_fut,
+            future: core::mem::ManuallyDrop::new(future),
+        };
+        // SAFETY: precondition (1) and (3)
+        unsafe { Self::drop_future(this) };
+        drop(write_guard);
+    }
+}
+
+impl<T, F: ?Sized, O, Obs> RawScope<T, F, O, Obs>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = O>,
+    Obs: ScopeObserver,
+{
+    /// # Panics
+    ///
+    /// - If [`RawScope::try_enter`] returns an error.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`RawScope::try_enter`].
+    pub(crate) unsafe fn enter<'borrow, I, Output, G>(
+        this: NonNull<Self>,
+        input: I,
+        f: G,
+    ) -> EnterOutcome<Output, &'borrow mut O>
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        // SAFETY: same preconditions
+        match unsafe { Self::try_enter(this, input, f) } {
+            Ok(outcome) => outcome,
+            // SAFETY: precondition (1)
+            Err(error) => unsafe { Self::fail(this, "enter", error) },
+        }
+    }
+
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O>`.
     /// 2. `this` verifies the guarantees of `Pin` (one of its fields is pinned in this function)
-    /// 3. No other exclusive reference to the frozen value. In particular, no concurrent calls to this function.
-    #[allow(unused_unsafe)]
-    pub(crate) unsafe fn enter<'borrow, Output, G>(this: NonNull<Self>, f: G) -> Output
+    /// 3. No other exclusive reference to the frozen value or to the output.
+    ///    In particular, no concurrent calls to this function.
+    /// 4. `I` is the input type of the scope that was opened in `this`.
+    pub(crate) unsafe fn try_enter<'borrow, I, Output, G>(
+        this: NonNull<Self>,
+        input: I,
+        f: G,
+    ) -> Result<EnterOutcome<Output, &'borrow mut O>, ScopeError>
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        // If the scope was not frozen yet, nobody takes the input and it is dropped at the end of this function.
+        let mut input = Some(input);
+
+        // SAFETY: preconditions (1) to (4)
+        let poll = unsafe {
+            Self::poll(
+                this,
+                &mut input,
+                &mut core::task::Context::from_waker(&waker::create()),
+            )
+        };
+        match poll {
+            Poll::Ready(Ok(())) => {}
+            Poll::Ready(Err(error)) => return Err(error),
+            Poll::Pending => return Err(ScopeError::ForeignFuture),
+        }
+
+        // SAFETY: preconditions (1) and (3)
+        unsafe { Self::access(this, f) }
+    }
+
+    /// Like [`RawScope::enter`], but parks the current thread while the future of the scope is waiting
+    /// on a future other than the [`FrozenFuture`], until that future wakes it.
+    ///
+    /// # Panics
+    ///
+    /// - If the scope is poisoned, or becomes poisoned.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`RawScope::try_enter`].
+    #[cfg(feature = "std")]
+    pub(crate) unsafe fn enter_blocking<'borrow, I, Output, G>(
+        this: NonNull<Self>,
+        input: I,
+        f: G,
+    ) -> EnterOutcome<Output, &'borrow mut O>
     where
         G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
     {
+        let mut input = Some(input);
+        let waker = waker::current_thread();
+        let mut cx = core::task::Context::from_waker(&waker);
+
+        let result = loop {
+            // SAFETY: preconditions (1) to (4)
+            match unsafe { Self::poll(this, &mut input, &mut cx) } {
+                // SAFETY: preconditions (1) and (3)
+                Poll::Ready(Ok(())) => break unsafe { Self::access(this, f) },
+                Poll::Ready(Err(error)) => break Err(error),
+                // spurious wake-ups only cause the future to be polled again
+                Poll::Pending => std::thread::park(),
+            }
+        };
+        match result {
+            Ok(outcome) => outcome,
+            // SAFETY: precondition (1)
+            Err(error) => unsafe { Self::fail(this, "enter", error) },
+        }
+    }
+
+    /// Polls the future of the scope once if it did not finish yet, making `input` available to the frozen future.
+    ///
+    /// Returns [`Poll::Ready`] if the scope is frozen, finished or poisoned, and [`Poll::Pending`] if the future
+    /// is waiting on a future other than the [`FrozenFuture`].
+    ///
+    /// The future of a scope frozen by [`TimeCapsule::freeze_forever`] is not polled anymore.
+    ///
+    /// The scope is poisoned if its future panics.
+    ///
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O>`.
+    /// 2. `this` verifies the guarantees of `Pin` (one of its fields is pinned in this function)
+    /// 3. No other exclusive reference to the frozen value or to the output.
+    ///    In particular, no concurrent calls to this function.
+    /// 4. `I` is the input type of the scope that was opened in `this`.
+    pub(crate) unsafe fn poll<I>(
+        this: NonNull<Self>,
+        input: &mut Option<I>,
+        cx: &mut core::task::Context<'_>,
+    ) -> Poll<Result<(), ScopeError>> {
         // SAFETY: precondition (1)
-        let RawScopeFields { state, active_fut } = unsafe { Self::fields(this.as_ptr()) };
+        let RawScopeFields {
+            state,
+            output,
+            observer,
+            active_fut,
+        } = unsafe { Self::fields(this.as_ptr()) };
+
+        // SAFETY: precondition (1)
+        if unsafe { (*state).poisoned } {
+            return Poll::Ready(Err(ScopeError::Poisoned));
+        }
+
+        // SAFETY: precondition (1) and (3)
+        let output = unsafe { &mut *output };
+        if output.is_some() {
+            return Poll::Ready(Ok(()));
+        }
+
+        // SAFETY: precondition (1) and (3)
+        if unsafe { core::mem::take(&mut (*state).primed) } {
+            return Poll::Ready(Ok(()));
+        }
+
+        // A value frozen forever stays frozen, so polling the future again would only freeze it again.
+        // SAFETY: precondition (1)
+        if unsafe { (*state).forever && (*state).frozen.is_some() } {
+            return Poll::Ready(Ok(()));
+        }
+
+        // SAFETY: precondition (1) and (3)
+        unsafe { (*state).started = true };
 
         // SAFETY: precondition (2)
         let active_fut: Pin<&mut F> = unsafe { Pin::new_unchecked(&mut *active_fut) };
 
-        match active_fut.poll(&mut core::task::Context::from_waker(&waker::create())) {
-            Poll::Ready(never) => match never {},
-            Poll::Pending => {}
+        // Make the input available to the frozen future while polling.
+        // SAFETY: precondition (1) and (3)
+        unsafe { (*state).input = (input as *mut Option<I>).cast() };
+        struct Guard<T: for<'a> Family<'a>>(*mut State<T>);
+        impl<T: for<'a> Family<'a>> Drop for Guard<T> {
+            fn drop(&mut self) {
+                // SAFETY: the state outlives the guard
+                unsafe { (*self.0).input = core::ptr::null_mut() }
+            }
+        }
+        // guard ensures the pointer to the input does not dangle, even if polling panics
+        let guard = Guard(state);
+        // SAFETY: precondition (1) and (3)
+        unsafe { (*observer).on_resume() };
+        // guard ensures a panicking future is never polled again
+        let poison_guard = PoisonGuard { state, observer };
+        let poll = active_fut.poll(cx);
+        core::mem::forget(poison_guard); // defuse guard
+        drop(guard);
+
+        match poll {
+            Poll::Ready(ready) => {
+                *output = Some(ready);
+                // SAFETY: precondition (1) and (3)
+                unsafe { (*observer).on_finish() };
+                Poll::Ready(Ok(()))
+            }
+            // SAFETY: precondition (1)
+            Poll::Pending if unsafe { (*state).frozen.is_some() } => {
+                // SAFETY: precondition (1) and (3)
+                unsafe { (*observer).on_freeze() };
+                Poll::Ready(Ok(()))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    /// Resumes the scope up to its next freeze, without accessing the frozen value.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`RawScope::try_enter`].
+    pub(crate) unsafe fn resume<I>(
+        this: NonNull<Self>,
+        input: I,
+    ) -> Result<ResumeOutcome, ScopeError> {
+        // If the scope was not frozen yet, nobody takes the input and it is dropped at the end of this function.
+        let mut input = Some(input);
+
+        // SAFETY: preconditions (1) to (4)
+        let poll = unsafe {
+            Self::poll(
+                this,
+                &mut input,
+                &mut core::task::Context::from_waker(&waker::create()),
+            )
+        };
+        match poll {
+            // SAFETY: precondition (1)
+            Poll::Ready(Ok(())) => Ok(unsafe { Self::outcome(this) }),
+            Poll::Ready(Err(error)) => Err(error),
+            Poll::Pending => Err(ScopeError::ForeignFuture),
+        }
+    }
+
+    /// Resumes the scope one last time with [`Resumed::Closing`] if it is frozen by [`TimeCapsule::freeze_resumed`],
+    /// so that it can clean up before being dropped.
+    ///
+    /// Does nothing if the scope is poisoned, finished, or not frozen by [`TimeCapsule::freeze_resumed`].
+    ///
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O>`.
+    /// 2. `this` verifies the guarantees of `Pin` (one of its fields is pinned in this function)
+    /// 3. No other reference to the frozen value or to the output.
+    ///    In particular, no concurrent calls to this function.
+    /// 4. `I` is the input type of the scope that was opened in `this`.
+    pub(crate) unsafe fn close<I>(this: NonNull<Self>) {
+        // SAFETY: precondition (1)
+        let RawScopeFields { state, output, .. } = unsafe { Self::fields(this.as_ptr()) };
+
+        // SAFETY: precondition (1) and (3)
+        unsafe {
+            if (*state).poisoned
+                || (*output).is_some()
+                || (*state).frozen.is_none()
+                || !(*state).closable
+            {
+                return;
+            }
+            (*state).closing = true;
+            // the frozen value must be resumed, even if it was reached by priming the scope
+            (*state).primed = false;
+        }
+
+        // A pending foreign future or a new freeze after cleaning up is ignored, as the scope is about to be dropped.
+        // SAFETY: preconditions (1) to (4), the input is never taken as the scope resolves to `Resumed::Closing`.
+        let _ = unsafe {
+            Self::poll(
+                this,
+                &mut None::<I>,
+                &mut core::task::Context::from_waker(&waker::create()),
+            )
+        };
+    }
+
+    /// Describes the current state of a scope that was resumed, or [`ResumeOutcome::Frozen`] if it was never resumed.
+    ///
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O>`.
+    pub(crate) unsafe fn outcome(this: NonNull<Self>) -> ResumeOutcome {
+        // SAFETY: precondition (1)
+        let RawScopeFields { state, output, .. } = unsafe { Self::fields(this.as_ptr()) };
+
+        // SAFETY: precondition (1)
+        if unsafe { (*output).is_some() } {
+            ResumeOutcome::Finished
+        } else if unsafe { (*state).frozen.is_some() && (*state).forever } {
+            ResumeOutcome::FrozenForever
+        } else {
+            ResumeOutcome::Frozen
+        }
+    }
+
+    /// Polls the future of the scope up to its first freeze, so that the next call to [`RawScope::poll`]
+    /// accesses the value of that freeze instead of resuming the scope.
+    ///
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O>`.
+    /// 2. `this` verifies the guarantees of `Pin` (one of its fields is pinned in this function)
+    /// 3. No other reference to the frozen value or to the output, and the scope was never polled.
+    /// 4. `I` is the input type of the scope that was opened in `this`.
+    pub(crate) unsafe fn prime<I>(this: NonNull<Self>) -> Result<(), ScopeError> {
+        // SAFETY: preconditions (1) to (4), the input is never taken as the scope is not frozen yet.
+        let poll = unsafe {
+            Self::poll(
+                this,
+                &mut None::<I>,
+                &mut core::task::Context::from_waker(&waker::create()),
+            )
+        };
+        match poll {
+            Poll::Ready(Ok(())) => {
+                // SAFETY: precondition (1)
+                let RawScopeFields { state, .. } = unsafe { Self::fields(this.as_ptr()) };
+                // SAFETY: precondition (1) and (3)
+                unsafe { (*state).primed = (*state).frozen.is_some() };
+                Ok(())
+            }
+            Poll::Ready(Err(error)) => Err(error),
+            Poll::Pending => Err(ScopeError::ForeignFuture),
+        }
+    }
+
+    /// Accesses the value currently frozen in the scope, or its output if it finished.
+    ///
+    /// The scope is poisoned if `f` panics, unless the value was frozen by [`TimeCapsule::freeze_resumed`].
+    ///
+    /// Returns [`ScopeError::FrozenShared`] if the scope is frozen through a shared reference.
+    ///
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O>`.
+    /// 2. No other exclusive reference to the frozen value or to the output.
+    pub(crate) unsafe fn access<'borrow, Output, G>(
+        this: NonNull<Self>,
+        f: G,
+    ) -> Result<EnterOutcome<Output, &'borrow mut O>, ScopeError>
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        // SAFETY: precondition (1)
+        let RawScopeFields {
+            state,
+            output,
+            observer,
+            ..
+        } = unsafe { Self::fields(this.as_ptr()) };
+
+        // SAFETY: precondition (1) and (2)
+        if let Some(output) = unsafe { &mut *output } {
+            return Ok(EnterOutcome::Finished(output));
+        }
+
+        // SAFETY: precondition (1)
+        let (frozen, shared) = unsafe { ((*state).frozen, (*state).shared) };
+        let Some(frozen) = frozen else {
+            return Err(ScopeError::NotFilled);
+        };
+        if shared {
+            return Err(ScopeError::FrozenShared);
         }
 
         // SAFETY:
         // - dereferenceable: precondition (1)
-        // - drop: reading a reference (no drop glue)
-        // - aliasing: precondition (3) + `mut_ref` cannot escape this function via `f`
+        // - aliasing: precondition (2) + `mut_ref` cannot escape this function via `f`,
+        //   and the value was frozen from an exclusive reference.
         // - lifetime: the value is still live due to the precondition on `Scope::run`,
         //   preventing <https://github.com/dureuill/nolife/issues/8>
-        let mut_ref = unsafe {
-            state
-                .read()
-                .expect("The scope's future did not fill the value")
-                .as_mut()
+        let mut_ref = unsafe { frozen.cast().as_mut() };
+
+        struct ConsumerGuard<T: for<'a> Family<'a>, Obs: ScopeObserver> {
+            state: *mut State<T>,
+            observer: *mut Obs,
+        }
+        impl<T: for<'a> Family<'a>, Obs: ScopeObserver> Drop for ConsumerGuard<T, Obs> {
+            fn drop(&mut self) {
+                // SAFETY: the state and the observer outlive the guard
+                let (state, observer) = unsafe { (&mut *self.state, &mut *self.observer) };
+                if state.closable {
+                    state.consumer_panicked = true;
+                } else {
+                    state.poisoned = true;
+                }
+                observer.on_panic();
+            }
+        }
+        // guard ensures that if `f` panics, as `f` might leave the frozen value in an inconsistent state,
+        // either the scope is notified when resumed, or the scope is poisoned if it cannot be notified.
+        // SAFETY: precondition (1) and (2)
+        unsafe { (*observer).on_enter() };
+        let consumer_guard = ConsumerGuard { state, observer };
+        let result = f(mut_ref);
+        core::mem::forget(consumer_guard); // defuse guard
+                                           // SAFETY: precondition (1) and (2)
+        unsafe { (*observer).on_exit() };
+
+        Ok(EnterOutcome::Entered(result))
+    }
+
+    /// Accesses the value currently frozen in the scope, without polling its future.
+    ///
+    /// # Errors
+    ///
+    /// - [`ScopeError::Poisoned`] if the scope is poisoned.
+    /// - [`ScopeError::NotStarted`] if the future of the scope was never polled.
+    /// - [`ScopeError::NotFilled`] if the scope finished.
+    /// - [`ScopeError::ForeignFuture`] if the future of the scope is waiting on a future other than the [`FrozenFuture`].
+    ///
+    /// # Panics
+    ///
+    /// - If the scope is frozen through a shared reference.
+    ///
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O>`.
+    /// 2. No other exclusive reference to the frozen value or to the output.
+    pub(crate) unsafe fn enter_current<'borrow, Output, G>(
+        this: NonNull<Self>,
+        f: G,
+    ) -> Result<Output, ScopeError>
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+        O: 'borrow,
+    {
+        // SAFETY: precondition (1)
+        let RawScopeFields { state, output, .. } = unsafe { Self::fields(this.as_ptr()) };
+
+        // SAFETY: precondition (1) and (2)
+        let (poisoned, started, frozen, finished) = unsafe {
+            (
+                (*state).poisoned,
+                (*state).started,
+                (*state).frozen.is_some(),
+                (*output).is_some(),
+            )
         };
+        if poisoned {
+            return Err(ScopeError::Poisoned);
+        }
+        if !started {
+            return Err(ScopeError::NotStarted);
+        }
+        if finished {
+            return Err(ScopeError::NotFilled);
+        }
+        if !frozen {
+            return Err(ScopeError::ForeignFuture);
+        }
+
+        // SAFETY: preconditions (1) and (2)
+        match unsafe { Self::access(this, f) }? {
+            EnterOutcome::Entered(output) => Ok(output),
+            EnterOutcome::Finished(_) => Err(ScopeError::NotFilled),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O>`.
+    pub(crate) unsafe fn state(this: NonNull<Self>) -> ScopeState {
+        // SAFETY: precondition (1)
+        let RawScopeFields { state, output, .. } = unsafe { Self::fields(this.as_ptr()) };
+
+        // SAFETY: precondition (1)
+        let (state, finished) = unsafe { (&*state, (*output).is_some()) };
+        if state.poisoned {
+            ScopeState::Poisoned
+        } else if finished {
+            ScopeState::Finished
+        } else if !state.started {
+            ScopeState::NotStarted
+        } else if state.frozen.is_none() {
+            ScopeState::Waiting
+        } else if state.forever {
+            ScopeState::FrozenForever
+        } else {
+            ScopeState::Frozen
+        }
+    }
+
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O>`.
+    pub(crate) unsafe fn last_freeze_location(
+        this: NonNull<Self>,
+    ) -> Option<&'static Location<'static>> {
+        // SAFETY: precondition (1)
+        let RawScopeFields { state, .. } = unsafe { Self::fields(this.as_ptr()) };
+
+        // SAFETY: precondition (1)
+        unsafe { (*state).location }
+    }
+
+    /// Describes the state of the scope and its last freeze, for panic messages.
+    ///
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O>`.
+    unsafe fn diagnostics(this: NonNull<Self>) -> Diagnostics {
+        Diagnostics {
+            // SAFETY: precondition (1)
+            state: unsafe { Self::state(this) },
+            // SAFETY: precondition (1)
+            location: unsafe { Self::last_freeze_location(this) },
+        }
+    }
+
+    /// Panics with a message describing `error` and the state of the scope.
+    ///
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O>`.
+    #[cold]
+    #[track_caller]
+    pub(crate) unsafe fn fail(this: NonNull<Self>, action: &str, error: ScopeError) -> ! {
+        // SAFETY: precondition (1)
+        let diagnostics = unsafe { Self::diagnostics(this) };
+        panic!("Cannot {action} the scope: {error} ({diagnostics})")
+    }
+
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O>`.
+    pub(crate) unsafe fn is_poisoned(this: NonNull<Self>) -> bool {
+        // SAFETY: precondition (1)
+        let RawScopeFields { state, .. } = unsafe { Self::fields(this.as_ptr()) };
+
+        // SAFETY: precondition (1)
+        unsafe { (*state).poisoned }
+    }
+
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O>`.
+    /// 2. No reference to the output.
+    pub(crate) unsafe fn take_output(this: NonNull<Self>) -> Option<O> {
+        // SAFETY: precondition (1)
+        let RawScopeFields { output, .. } = unsafe { Self::fields(this.as_ptr()) };
+
+        // SAFETY: precondition (1) and (2)
+        unsafe { (*output).take() }
+    }
+}
+
+impl<T, F: ?Sized, O, E, Obs> RawScope<T, F, Result<O, E>, Obs>
+where
+    T: for<'a> Family<'a>,
+{
+    /// Takes the output of the scope if it finished with an error.
+    ///
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, Result<O, E>>`.
+    /// 2. No reference to the output.
+    pub(crate) unsafe fn take_error(this: NonNull<Self>) -> Option<E> {
+        // SAFETY: precondition (1)
+        let RawScopeFields { output, .. } = unsafe { Self::fields(this.as_ptr()) };
+
+        // SAFETY: precondition (1) and (2)
+        let output = unsafe { &mut *output };
+        if let Some(Err(_)) = output {
+            output.take().and_then(Result::err)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T, F: ?Sized, O, Obs> RawScope<T, F, O, Obs>
+where
+    T: for<'a> Family<'a>,
+{
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O>`.
+    /// 2. No exclusive reference to the frozen value, and no call to [`RawScope::enter`] for `'borrow`.
+    pub(crate) unsafe fn enter_shared<'borrow, Output, G>(
+        this: NonNull<Self>,
+        f: G,
+    ) -> Option<Output>
+    where
+        G: for<'a> FnOnce(&'borrow <T as Family<'a>>::Family) -> Output,
+    {
+        // SAFETY: precondition (1)
+        let RawScopeFields { state, .. } = unsafe { Self::fields(this.as_ptr()) };
+
+        // The frozen value of a poisoned scope might be left in an inconsistent state.
+        // SAFETY: precondition (1)
+        if unsafe { (*state).poisoned } {
+            return None;
+        }
+
+        // SAFETY: precondition (1)
+        let frozen = unsafe { (*state).frozen }?;
+
+        // SAFETY:
+        // - dereferenceable: precondition (1)
+        // - aliasing: precondition (2), the future is not polled for `'borrow`.
+        // - lifetime: the value is still live as the future is suspended on the frozen value.
+        let shared_ref = unsafe { frozen.cast().as_ref() };
+
+        Some(f(shared_ref))
+    }
 
-        f(mut_ref)
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O, Obs>`.
+    /// 2. No exclusive reference to the observer for `'borrow`.
+    pub(crate) unsafe fn observer<'borrow>(this: NonNull<Self>) -> &'borrow Obs {
+        // SAFETY: precondition (1)
+        let RawScopeFields { observer, .. } = unsafe { Self::fields(this.as_ptr()) };
+
+        // SAFETY: precondition (1) and (2)
+        unsafe { &*observer }
     }
+
+    /// Notifies the observer that the scope is about to be dropped.
+    ///
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `RawScope<T, F, O, Obs>`.
+    /// 2. No reference to the observer.
+    pub(crate) unsafe fn notify_drop(this: NonNull<Self>)
+    where
+        Obs: ScopeObserver,
+    {
+        // SAFETY: precondition (1)
+        let RawScopeFields { observer, .. } = unsafe { Self::fields(this.as_ptr()) };
+
+        // SAFETY: precondition (1) and (2)
+        unsafe { (*observer).on_drop() }
+    }
+}
+
+// SAFETY: a `FrozenFuture` is semantically a `&'a mut <T as Family<'b>>::Family`,
+// so it is `Send` if the frozen data is `Send`.
+unsafe impl<'a, 'b, T, I> Send for FrozenFuture<'a, 'b, T, I>
+where
+    T: for<'c> Family<'c>,
+    <T as Family<'b>>::Family: Send,
+{
 }
 
-impl<'a, 'b, T> Future for FrozenFuture<'a, 'b, T>
+impl<'a, 'b, T, I> Future for FrozenFuture<'a, 'b, T, I>
 where
     T: for<'c> Family<'c>,
 {
-    type Output = ();
+    type Output = I;
 
     fn poll(
         mut self: core::pin::Pin<&mut Self>,
@@ -229,17 +1223,87 @@ where
         // - state was set to a valid value in [`TimeCapsule::freeze`]
         // - the value is still 'live', due to the lifetime in `FrozenFuture`
         let state: &mut State<T> = unsafe { &mut *self.state };
-        if state.is_none() {
-            let mut_ref = self
-                .mut_ref
+        if state.frozen.is_none() {
+            let frozen = self
+                .frozen
                 .take()
                 .expect("poll called several times on the same future");
 
-            *state = Some(mut_ref);
+            state.frozen = Some(frozen);
+            state.shared = self.shared;
+            state.forever = self.forever;
+            state.closable = self.closable;
+            state.location = Some(self.location);
             Poll::Pending
         } else {
-            *state = None;
-            Poll::Ready(())
+            // SAFETY: `state.input` is either null or points to the `Option<I>` input of the current call to
+            // `RawScope::enter`. The type is `I` because the `TimeCapsule<T, I>` of this scope was created with
+            // the input type of the scope.
+            let input = unsafe { state.input.cast::<Option<I>>().as_mut() }
+                .and_then(Option::take)
+                .expect("The scope was resumed without an input");
+            state.frozen = None;
+            Poll::Ready(input)
+        }
+    }
+}
+
+impl<'a, 'b, T, I> Future for ResumedFuture<'a, 'b, T, I>
+where
+    T: for<'c> Family<'c>,
+{
+    type Output = Resumed<I>;
+
+    fn poll(
+        mut self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> Poll<Self::Output> {
+        // SAFETY:
+        // - state was set to a valid value in [`TimeCapsule::freeze_resumed`]
+        // - the value is still 'live', due to the lifetime in `FrozenFuture`
+        let state: &mut State<T> = unsafe { &mut *self.0.state };
+        if state.frozen.is_some() {
+            if state.closing {
+                state.frozen = None;
+                return Poll::Ready(Resumed::Closing);
+            }
+            if core::mem::take(&mut state.consumer_panicked) {
+                return Pin::new(&mut self.0)
+                    .poll(cx)
+                    .map(Resumed::ConsumerPanicked);
+            }
+        }
+        Pin::new(&mut self.0).poll(cx).map(Resumed::Normal)
+    }
+}
+
+// SAFETY: a `FrozenForeverFuture` is semantically a `&'a mut <T as Family<'b>>::Family`,
+// so it is `Send` if the frozen data is `Send`.
+unsafe impl<'a, 'b, T, I> Send for FrozenForeverFuture<'a, 'b, T, I>
+where
+    T: for<'c> Family<'c>,
+    <T as Family<'b>>::Family: Send,
+{
+}
+
+impl<'a, 'b, T, I> Future for FrozenForeverFuture<'a, 'b, T, I>
+where
+    T: for<'c> Family<'c>,
+{
+    type Output = Never;
+
+    fn poll(
+        mut self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> Poll<Self::Output> {
+        loop {
+            match Pin::new(&mut self.future).poll(cx) {
+                Poll::Ready(_input) => {
+                    // freeze the same value again
+                    self.future.frozen = self.value;
+                }
+                Poll::Pending => return Poll::Pending,
+            }
         }
     }
 }
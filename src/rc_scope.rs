@@ -0,0 +1,285 @@
+use alloc::rc::{Rc, Weak};
+use core::{cell::Cell, cell::UnsafeCell, future::Future, mem::MaybeUninit, ptr::NonNull};
+
+use crate::{raw_scope::RawScope, AlreadyEntered, Family, Never, TopScope};
+
+// SAFETY: repr C to ensure conversion between RcInner<T, MaybeUninit<F>> and RcInner<T, F>
+// does not rely on unstable memory layout.
+#[repr(C)]
+struct RcInner<T, F: ?Sized>
+where
+    T: for<'a> Family<'a>,
+{
+    entered: Cell<bool>,
+    raw_scope: UnsafeCell<RawScope<T, F>>,
+}
+
+/// A dynamic scope tied to a reference-counted allocation.
+///
+/// Cloning a `RcScope` is cheap and yields a handle to the same scope.
+/// In exchange, [`RcScope::enter`] only takes a shared reference and can fail if the scope is already entered.
+///
+/// Like [`crate::BoxScope`], this kind of scopes uses a single dynamic allocation.
+pub struct RcScope<T, F: ?Sized = dyn Future<Output = Never> + 'static>(Rc<RcInner<T, F>>)
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>;
+
+/// A weak handle to a [`RcScope`].
+///
+/// Obtained by calling [`RcScope::downgrade`], it does not keep the scope alive.
+pub struct WeakRcScope<T, F: ?Sized = dyn Future<Output = Never> + 'static>(Weak<RcInner<T, F>>)
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>;
+
+impl<T, F: ?Sized> Clone for RcScope<T, F>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T, F: ?Sized> Clone for WeakRcScope<T, F>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> RcScope<T>
+where
+    T: for<'a> Family<'a>,
+{
+    /// Ties the passed scope to a reference-counted allocation.
+    ///
+    /// This function erased the `Future` generic type of the [`TopScope`], at the cost
+    /// of using a dynamic function call to poll the future.
+    ///
+    /// If the `Future` generic type can be inferred, it can be more efficient to use [`RcScope::new`].
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics.
+    pub fn new_dyn<S: TopScope<Family = T>>(scope: S) -> Self
+    where
+        S::Future: 'static,
+    {
+        let this = RcScope::new(scope);
+        Self(this.0)
+    }
+}
+
+impl<T, F> RcScope<T, F>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    /// Ties the passed scope to a reference-counted allocation.
+    ///
+    /// This function retains the `Future` generic type from the [`TopScope`].
+    /// To store the [`RcScope`] in a struct, it can be easier to use [`RcScope::new_dyn`].
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics.
+    pub fn new<S: TopScope<Family = T, Future = F>>(scope: S) -> RcScope<T, F> {
+        let inner = Rc::new(RcInner {
+            entered: Cell::new(false),
+            raw_scope: UnsafeCell::new(RawScope::<T, F>::new_uninit(())),
+        });
+        let inner: *const RcInner<T, MaybeUninit<F>> = Rc::into_raw(inner);
+        struct Guard<T, F>
+        where
+            T: for<'a> Family<'a>,
+        {
+            inner: *const RcInner<T, MaybeUninit<F>>,
+        }
+        // guard ensures the Rc is freed on panic (i.e. if scope.run panics)
+        let panic_guard = Guard { inner };
+        impl<T, F> Drop for Guard<T, F>
+        where
+            T: for<'a> Family<'a>,
+        {
+            fn drop(&mut self) {
+                // SAFETY: defuse below makes sure this only happens on panic,
+                // in this case, self.inner is still in the same uninitialized state
+                // and not otherwise being cleaned up, so this `Rc::from_raw` pairs with
+                // `Rc::into_raw` above
+                drop(unsafe { Rc::from_raw(self.inner) })
+            }
+        }
+
+        // SAFETY: `inner` comes from `Rc::into_raw` and so points to a live `RcInner`.
+        let raw_scope: *mut RawScope<T, F> = unsafe { (*inner).raw_scope.get() }.cast();
+
+        // SAFETY:
+        // 1. `raw_scope` allocated by the `Rc` so is valid memory, although the future is not yet initialized
+        // 2. `raw_scope` was created from a valid `RawScope::<T, MaybeUninit<F>>`, so `state` is fully initialized.
+        //
+        // Note: as a post-condition of `RawScope`, `raw_scope` is fully initialized.
+        unsafe {
+            RawScope::open(raw_scope, scope);
+        }
+
+        core::mem::forget(panic_guard); // defuse guard
+
+        // SAFETY: this `Rc::from_raw` pairs with the `Rc::into_raw` above.
+        // The type `F` is not the same, but `MaybeUninit<F>` and `F` are repr(transparent)-compatible
+        // and `RcInner` and `RawScope` are repr(C), so both types have the same size and alignment.
+        // Furthermore, `RawScope::open` ensured that F is properly initialized so it may be dropped.
+        RcScope(unsafe { Rc::from_raw(inner.cast()) })
+    }
+}
+
+impl<T, F: ?Sized> RcScope<T, F>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    /// Enters the scope, making it possible to access the data frozen inside of the scope.
+    ///
+    /// # Errors
+    ///
+    /// - [`AlreadyEntered`] if this function is called again while the scope is entered,
+    ///   either from `f` or from the underlying future.
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    /// - If the scope is poisoned, see [`RcScope::is_poisoned`].
+    /// - If the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    pub fn enter<Output, G>(&self, f: G) -> Result<Output, AlreadyEntered>
+    where
+        G: for<'borrow, 'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        if self.0.entered.replace(true) {
+            return Err(AlreadyEntered);
+        }
+
+        struct Guard<'a>(&'a Cell<bool>);
+        impl Drop for Guard<'_> {
+            fn drop(&mut self) {
+                self.0.set(false)
+            }
+        }
+        // guard ensures the scope can be entered again after this call, even on panic
+        let _guard = Guard(&self.0.entered);
+
+        // SAFETY: `UnsafeCell::get` never returns a null pointer.
+        let raw_scope = unsafe { NonNull::new_unchecked(self.0.raw_scope.get()) };
+
+        // SAFETY:
+        // 1. `raw_scope` is valid as a post-condition of `new`.
+        // 2. The object pointed to by `raw_scope` is owned by the `Rc` and won't move before deallocation.
+        // 3. The `entered` flag guarantees that no other call to `RawScope::enter` is in progress,
+        //    and the reference passed to `f` cannot escape `f`.
+        // 4. `new` only accepts scopes with `()` as input.
+        Ok(unsafe { RawScope::enter(raw_scope, (), f) }.into_entered())
+    }
+
+    /// Creates a new [`WeakRcScope`] handle to this scope.
+    pub fn downgrade(this: &Self) -> WeakRcScope<T, F> {
+        WeakRcScope(Rc::downgrade(&this.0))
+    }
+
+    /// Returns `true` if the scope is poisoned.
+    ///
+    /// A scope becomes poisoned when its underlying future panics, or when a function passed to enter the scope panics,
+    /// as the frozen data might then be left in an inconsistent state.
+    /// Entering a poisoned scope panics.
+    pub fn is_poisoned(&self) -> bool {
+        // SAFETY:
+        // 1. `UnsafeCell::get` never returns a null pointer.
+        // 2. the scope is valid as a post-condition of `new`.
+        unsafe { RawScope::is_poisoned(NonNull::new_unchecked(self.0.raw_scope.get())) }
+    }
+
+    /// Returns `true` if the two `RcScope`s are handles to the same scope.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Rc::ptr_eq(&this.0, &other.0)
+    }
+}
+
+impl<T, F: ?Sized> WeakRcScope<T, F>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    /// Attempts to upgrade this handle to a [`RcScope`].
+    ///
+    /// Returns `None` if the scope has been dropped.
+    pub fn upgrade(&self) -> Option<RcScope<T, F>> {
+        self.0.upgrade().map(RcScope)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{scope, SingleFamily};
+
+    #[test]
+    fn shared_enter() {
+        let scope = RcScope::<SingleFamily<u32>>::new_dyn(scope!({
+            let mut x = 0u32;
+            loop {
+                freeze!(&mut x);
+                x += 1;
+            }
+        }));
+        let other = scope.clone();
+
+        assert_eq!(scope.enter(|x| *x + 42), Ok(42));
+        assert_eq!(other.enter(|x| *x + 42), Ok(43));
+        assert!(RcScope::ptr_eq(&scope, &other));
+    }
+
+    #[test]
+    fn reentrant_enter() {
+        let scope = RcScope::<SingleFamily<u32>, _>::new(scope!({ freeze_forever!(&mut 42) }));
+        let other = scope.clone();
+
+        assert_eq!(
+            scope.enter(|x| {
+                assert_eq!(*x, 42);
+                other.enter(|_| ())
+            }),
+            Ok(Err(AlreadyEntered))
+        );
+        assert_eq!(other.enter(|x| *x), Ok(42));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn poisoned_after_panic() {
+        let scope = RcScope::<SingleFamily<u32>, _>::new(scope!({ freeze_forever!(&mut 42) }));
+
+        assert!(std::panic::catch_unwind(core::panic::AssertUnwindSafe(
+            || scope.enter(|_| panic!())
+        ))
+        .is_err());
+        assert!(scope.is_poisoned());
+        assert!(
+            std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| scope.enter(|x| *x)))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn weak_handle() {
+        let scope = RcScope::<SingleFamily<u32>>::new_dyn(scope!({ freeze_forever!(&mut 42) }));
+        let weak = RcScope::downgrade(&scope);
+
+        assert_eq!(weak.upgrade().unwrap().enter(|x| *x), Ok(42));
+        drop(scope);
+        assert!(weak.upgrade().is_none());
+    }
+}
@@ -1,5 +1,7 @@
-//! Defines a generic `Scope` as a trait that can be instantiated as a [`crate::BoxScope`].
-use core::{future::Future, marker::PhantomData};
+//! Defines a generic `Scope` as a trait that can be instantiated as a
+#![cfg_attr(feature = "alloc", doc = "[`crate::BoxScope`].")]
+#![cfg_attr(not(feature = "alloc"), doc = "`BoxScope`.")]
+use core::{alloc::Layout, future::Future, marker::PhantomData};
 
 use crate::{Family, Never, TimeCapsule};
 
@@ -8,9 +10,9 @@ use crate::{Family, Never, TimeCapsule};
 /// The trait is only implemented on [`crate::scope::Wrapper`]
 pub(crate) trait Sealed {}
 
-impl<P, Family, Future, Output> Sealed for Wrapper<P, Family, Future, Output>
+impl<P, Family, Future, Output, Input> Sealed for Wrapper<P, Family, Future, Output, Input>
 where
-    P: FnOnce(super::TimeCapsule<Family>) -> Future,
+    P: FnOnce(super::TimeCapsule<Family, Input>) -> Future,
     Family: for<'a> crate::Family<'a>,
     Future: core::future::Future<Output = Output>,
 {
@@ -18,9 +20,13 @@ where
 
 /// A scope that can be frozen in time.
 ///
+/// `Input` is the type of the values passed to the scope when resuming it, see
+#[cfg_attr(feature = "alloc", doc = "[`crate::BoxScope::enter_with`].")]
+#[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::enter_with`.")]
+///
 /// To get a `Scope`, use the [`crate::scope!`] macro.
 #[allow(private_bounds)]
-pub trait Scope: Sealed {
+pub trait Scope<Input = ()>: Sealed {
     /// The helper struct that serves to define the reference type.
     type Family: for<'a> Family<'a>;
     /// The output type of this scope.
@@ -28,6 +34,35 @@ pub trait Scope: Sealed {
     /// The underlying future that serves as a coroutine to freeze the scope.
     type Future: Future<Output = Self::Output>;
 
+    /// The layout of the underlying future of this scope.
+    ///
+    /// The future of an `async` block holds all the variables that live across its `.await` points,
+    /// so that its size grows with the data frozen by the scope.
+    /// See
+    #[cfg_attr(feature = "alloc", doc = "[`crate::BoxScope::new_bounded`]")]
+    #[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::new_bounded`")]
+    /// to enforce a size budget at compile time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nolife::{scope, Scope, SingleFamily, TopScope};
+    ///
+    /// fn buffer() -> impl TopScope<Family = SingleFamily<[u8; 1024]>> {
+    ///     scope!({
+    ///         let mut buffer = [0u8; 1024];
+    ///         freeze_forever!(&mut buffer)
+    ///     })
+    /// }
+    ///
+    /// fn layout_of<S: Scope>(_scope: &S) -> core::alloc::Layout {
+    ///     S::FUTURE_LAYOUT
+    /// }
+    ///
+    /// assert!(layout_of(&buffer()).size() >= 1024);
+    /// ```
+    const FUTURE_LAYOUT: Layout = Layout::new::<Self::Future>();
+
     /// Runs a scope by injecting a [`TimeCapsule`].
     ///
     /// # Safety
@@ -35,28 +70,32 @@ pub trait Scope: Sealed {
     /// - This function is only safe if the produced future is awaited immediately.
     ///
     /// Using the `sub_scope` macro inside a [`crate::scope!`] always verifies this condition and is therefore always safe.
-    unsafe fn run(self, time_capsule: TimeCapsule<Self::Family>) -> Self::Future;
+    unsafe fn run(self, time_capsule: TimeCapsule<Self::Family, Input>) -> Self::Future;
 }
 
 /// A top-level [`Scope`], always returning [`crate::Never`].
 ///
 /// Create one using the [`crate::scope!`] macro.
-pub trait TopScope: Scope<Output = Never> {}
+pub trait TopScope<Input = ()>: Scope<Input, Output = Never> {}
 
-impl<S> TopScope for S where S: Scope<Output = Never> {}
+impl<S, Input> TopScope<Input> for S where S: Scope<Input, Output = Never> {}
 
 /// A wrapper for a producer.
 ///
 /// See [`Scope`] for more information.
-struct Wrapper<P, Family, Future, Output>(P, PhantomData<*const Family>)
+struct Wrapper<P, Family, Future, Output, Input>(
+    P,
+    PhantomData<*const Family>,
+    PhantomData<fn(Input)>,
+)
 where
-    P: FnOnce(TimeCapsule<Family>) -> Future,
+    P: FnOnce(TimeCapsule<Family, Input>) -> Future,
     Family: for<'a> crate::Family<'a>,
     Future: core::future::Future<Output = Output>;
 
-impl<P, Family, Future, Output> Scope for Wrapper<P, Family, Future, Output>
+impl<P, Family, Future, Output, Input> Scope<Input> for Wrapper<P, Family, Future, Output, Input>
 where
-    P: FnOnce(TimeCapsule<Family>) -> Future,
+    P: FnOnce(TimeCapsule<Family, Input>) -> Future,
     Family: for<'a> crate::Family<'a>,
     Future: core::future::Future<Output = Output>,
 {
@@ -64,7 +103,7 @@ where
     type Output = Output;
     type Future = Future;
 
-    unsafe fn run(self, time_capsule: TimeCapsule<Self::Family>) -> Self::Future {
+    unsafe fn run(self, time_capsule: TimeCapsule<Self::Family, Input>) -> Self::Future {
         (self.0)(time_capsule)
     }
 }
@@ -79,38 +118,74 @@ where
 ///   and that the resulting future is awaited immediately.
 ///
 /// Using the [`crate::scope!`] macro always verifies this condition and is therefore always safe.
-pub unsafe fn new_scope<P, Family, Future, Output>(
+pub unsafe fn new_scope<P, Family, Future, Output, Input>(
     producer: P,
-) -> impl Scope<Family = Family, Output = Output, Future = Future>
+) -> impl Scope<Input, Family = Family, Output = Output, Future = Future>
 where
-    P: FnOnce(TimeCapsule<Family>) -> Future,
+    P: FnOnce(TimeCapsule<Family, Input>) -> Future,
     Family: for<'a> crate::Family<'a>,
     Future: core::future::Future<Output = Output>,
 {
-    Wrapper(producer, PhantomData)
+    Wrapper(producer, PhantomData, PhantomData)
 }
 
 /// A macro to open a scope that can be frozen in time.
 ///
-/// You can write code like you normally would in that scope, but you get 3 additional superpowers:
+/// You can write code like you normally would in that scope, but you get 5 additional superpowers:
 ///
-/// 1. `freeze!(&mut x)`: interrupts execution of the scope until the next call to [`crate::BoxScope::enter`],
-///   that will resume execution. The passed `&mut x` will be available to the next call to [`crate::BoxScope::enter`].
-/// 2. `freeze_forever!(&mut x)`: interrupts execution of the scope forever.
-///    All future calls to [`crate::BoxScope::enter`] will have access to the passed `&mut x`.
-/// 3. `subscope!(some_subscope(...))`: execute an expression that can be another function returning a `scope!` itself.
+/// 1. `freeze!(&mut x)`: interrupts execution of the scope until the next call to
+#[cfg_attr(feature = "alloc", doc = "[`crate::BoxScope::enter`],")]
+#[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::enter`,")]
+///    that will resume execution. The passed `&mut x` will be available to the next call to
+#[cfg_attr(feature = "alloc", doc = "[`crate::BoxScope::enter`].")]
+#[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::enter`.")]
+/// 2. `freeze_ref!(&x)`: like `freeze!`, but the passed `&x` will only be available through
+#[cfg_attr(feature = "alloc", doc = "[`crate::BoxScope::enter_shared`].")]
+#[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::enter_shared`.")]
+///    This allows the scope to keep other shared references to `x`.
+/// 3. `freeze_resumed!(&mut x)`: like `freeze!`, but evaluates to a [`crate::Resumed`] value,
+///    that is [`crate::Resumed::Closing`] when the scope is closed with
+#[cfg_attr(feature = "alloc", doc = "[`crate::BoxScope::close`],")]
+#[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::close`,")]
+///    or [`crate::Resumed::ConsumerPanicked`] when the function passed to enter the scope panicked.
+///    This allows the scope to clean up before it is dropped, or to restore the invariants of `x`.
+/// 4. `freeze_forever!(&mut x)`: interrupts execution of the scope forever.
+///    All future calls to
+#[cfg_attr(feature = "alloc", doc = "[`crate::BoxScope::enter`]")]
+#[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::enter`")]
+///    will have access to the passed `&mut x`.
+/// 5. `subscope!(some_subscope(...))`: execute an expression that can be another function returning a `scope!` itself.
 ///    This is meant to be able to structure your code in functions.
 ///
 /// A `scope!` invocation returns some type that `impl Scope` or `impl TopScope` (when the scope never returns).
 /// The `Family` type of the `Scope` typically needs to be annotated, whereas the `Future` type should not be.
 ///
+/// A scope that returns can still be opened in a
+#[cfg_attr(
+    feature = "alloc",
+    doc = "[`crate::BoxScope`], whose [`crate::BoxScope::enter_finite`]"
+)]
+#[cfg_attr(
+    not(feature = "alloc"),
+    doc = "`BoxScope`, whose `BoxScope::enter_finite`"
+)]
+/// and
+#[cfg_attr(feature = "alloc", doc = "[`crate::BoxScope::into_output`]")]
+#[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::into_output`")]
+/// methods give access to the output of the scope once it finished.
+///
+/// A scope can also accept inputs when it is resumed, by specifying their type with `scope!(input: T, { ... })`.
+/// In such a scope, `freeze!` and `freeze_ref!` evaluate to the input passed to
+#[cfg_attr(feature = "alloc", doc = "[`crate::BoxScope::enter_with`].")]
+#[cfg_attr(not(feature = "alloc"), doc = "`BoxScope::enter_with`.")]
+///
 ///
 /// # Using a subscope
 ///
 /// subscopes are useful to split your logic into smaller units (like functions),
 /// and in particular for error handling.
 /// ```
-/// use nolife::{BoxScope, Scope, SingleFamily, TopScope, scope};
+/// use nolife::{Scope, SingleFamily, TopScope, scope};
 ///
 /// fn outer_scope(
 ///     input_data: Vec<impl std::io::Read>,
@@ -144,7 +219,9 @@ where
 /// ```
 /// # Using a scope with a reference in input
 ///
-/// ```
+// Without `alloc`, `BoxScope` is not available to run the example.
+#[cfg_attr(feature = "alloc", doc = "```")]
+#[cfg_attr(not(feature = "alloc"), doc = "```ignore")]
 /// use nolife::{SingleFamily, BoxScope, TopScope, scope};
 ///
 /// fn scope_with_ref<'scope, 'a: 'scope>(
@@ -161,11 +238,19 @@ where
 /// # Panics
 ///
 /// The block passed to `scope` is technically an `async` block, but trying to `await` a future in this block
-/// will always result in a panic.
+/// will result in a panic, unless the scope is opened in a
+#[cfg_attr(feature = "alloc", doc = "[`crate::AsyncBoxScope`],")]
+#[cfg_attr(not(feature = "alloc"), doc = "`AsyncBoxScope`,")]
+/// or entered with
+#[cfg_attr(feature = "std", doc = "[`crate::BoxScope::enter_blocking`].")]
+#[cfg_attr(not(feature = "std"), doc = "`BoxScope::enter_blocking`.")]
 #[macro_export]
 macro_rules! scope {
     ($b:block) => {
-        match move |#[allow(unused_variables, unused_mut)] mut time_capsule| async move {
+        $crate::scope!(input: (), $b)
+    };
+    (input: $input:ty, $b:block) => {
+        match move |#[allow(unused_variables, unused_mut)] mut time_capsule: $crate::TimeCapsule<_, $input>| async move {
             'check_top: {
                 #[allow(unreachable_code)]
                 if false {
@@ -175,14 +260,47 @@ macro_rules! scope {
                 /// to [`nolife::BoxScope::enter`].
                 ///
                 /// Execution will resume after a call to [`nolife::BoxScope::enter`].
+                /// `freeze!` evaluates to the input passed to [`nolife::BoxScope::enter_with`].
                 #[allow(unused_macros)]
                 macro_rules! freeze {
-                    ($e:expr) => {
+                    ($e:expr) => {{
+                        #[allow(unreachable_code)]
+                        if false {
+                            break 'check_top (loop {});
+                        }
+                        $crate::TimeCapsule::freeze(&mut time_capsule, $e).await}
+                    }
+                }
+                /// `freeze_ref!(&x)` interrupts execution of the scope, making `&x` available to the next calls
+                /// to [`nolife::BoxScope::enter_shared`].
+                ///
+                /// Execution will resume after a call to [`nolife::BoxScope::enter`].
+                /// `freeze_ref!` evaluates to the input passed to [`nolife::BoxScope::enter_with`].
+                #[allow(unused_macros)]
+                macro_rules! freeze_ref {
+                    ($e:expr) => {{
+                        #[allow(unreachable_code)]
+                        if false {
+                            break 'check_top (loop {});
+                        }
+                        $crate::TimeCapsule::freeze_ref(&mut time_capsule, $e).await}
+                    }
+                }
+                /// `freeze_resumed!(&mut x)` interrupts execution of the scope, making `&mut x` available to the next call
+                /// to [`nolife::BoxScope::enter`].
+                ///
+                /// Execution will resume after a call to [`nolife::BoxScope::enter`] or [`nolife::BoxScope::close`].
+                /// `freeze_resumed!` evaluates to [`nolife::Resumed::Closing`] when the scope is closed,
+                /// to [`nolife::Resumed::ConsumerPanicked`] if the function passed to enter the scope panicked,
+                /// and to [`nolife::Resumed::Normal`] with the input passed to [`nolife::BoxScope::enter_with`] otherwise.
+                #[allow(unused_macros)]
+                macro_rules! freeze_resumed {
+                    ($e:expr) => {{
                         #[allow(unreachable_code)]
                         if false {
                             break 'check_top (loop {});
                         }
-                        $crate::TimeCapsule::freeze(&mut time_capsule, $e).await
+                        $crate::TimeCapsule::freeze_resumed(&mut time_capsule, $e).await}
                     }
                 }
                 /// `freeze_forever!(&mut x)` stops execution of the scope forever, making `&mut x` available to all future calls
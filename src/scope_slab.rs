@@ -0,0 +1,445 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    future::Future,
+    marker::PhantomData,
+    mem::MaybeUninit,
+    ptr::{self, addr_of_mut, NonNull},
+};
+
+use crate::{raw_scope::RawScope, Family, Never, TopScope};
+
+/// The number of scopes in a chunk of a [`ScopeSlab`].
+const CHUNK_LEN: usize = 64;
+
+/// A collection of scopes with the same future type, stored in shared chunks of memory.
+///
+/// Unlike a [`crate::BoxScope`], that uses an allocation per scope, a `ScopeSlab` allocates chunks that
+/// hold many scopes at once. The chunks never move nor are freed before the slab is dropped,
+/// so the scopes they hold stay in place while opened.
+///
+/// Scopes are accessed through the [`ScopeHandle`] returned by [`ScopeSlab::insert`].
+/// Once a scope is removed, its handle is invalidated, and its memory is reused by the next inserted scope.
+///
+/// `I` is the type of the inputs passed to the scopes when resuming them, see [`ScopeSlab::enter_with`].
+///
+/// # Example
+///
+/// ```
+/// use nolife::{scope, ScopeSlab, SingleFamily, TopScope};
+///
+/// fn count_words(text: String) -> impl TopScope<Family = SingleFamily<usize>> {
+///     scope!({ freeze_forever!(&mut text.split_whitespace().count()) })
+/// }
+///
+/// let mut slab = ScopeSlab::new();
+/// let short = slab.insert(count_words("Intel".to_string()));
+/// let long = slab.insert(count_words("Intel the Beagle".to_string()));
+///
+/// assert_eq!(slab.enter(short, |count| *count), Some(1));
+/// assert_eq!(slab.enter(long, |count| *count), Some(3));
+///
+/// assert!(slab.remove(short));
+/// assert_eq!(slab.enter(short, |count| *count), None);
+/// ```
+pub struct ScopeSlab<T, F, I = ()>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    /// Chunks of `CHUNK_LEN` slots, allocated by a `Box` and only freed when the slab is dropped.
+    chunks: Vec<NonNull<[Slot<T, F>]>>,
+    /// Indices of the unoccupied slots.
+    free: Vec<usize>,
+    len: usize,
+    marker: PhantomData<fn(I)>,
+}
+
+struct Slot<T, F>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    /// Incremented each time the scope of the slot is removed, to invalidate its handles.
+    ///
+    /// A slot whose generation reached `u32::MAX` is retired instead, and never reused.
+    generation: u32,
+    /// `true` if `raw_scope` is fully initialized.
+    occupied: bool,
+    raw_scope: MaybeUninit<RawScope<T, F>>,
+}
+
+/// A handle to a scope of a [`ScopeSlab`].
+///
+/// A handle is invalidated when its scope is removed, even if another scope is inserted in its place:
+/// a handle never gives access to a scope inserted after its own scope was removed.
+/// To uphold this guarantee, a slot is never reused once it held `u32::MAX + 1` scopes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScopeHandle {
+    index: usize,
+    generation: u32,
+}
+
+// SAFETY: same as `BoxScope`: the futures are polled and dropped, and the frozen data accessed,
+// by the thread owning the slab.
+unsafe impl<T, F, I> Send for ScopeSlab<T, F, I>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never> + Send,
+    for<'a> <T as Family<'a>>::Family: Send,
+{
+}
+
+impl<T, F, I> Default for ScopeSlab<T, F, I>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, F, I> ScopeSlab<T, F, I>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    /// Creates an empty slab, that does not allocate until a scope is inserted.
+    pub fn new() -> Self {
+        Self {
+            chunks: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+            marker: PhantomData,
+        }
+    }
+
+    /// The number of scopes in the slab.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the slab contains no scope.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if the scope of `handle` was not removed.
+    pub fn contains(&self, handle: ScopeHandle) -> bool {
+        self.slot(handle).is_some()
+    }
+
+    /// Opens the passed scope in the slab, returning a handle to enter it.
+    ///
+    /// Allocates a new chunk of scopes if all the slots of the slab are occupied.
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics.
+    pub fn insert<S: TopScope<I, Family = T, Future = F>>(&mut self, scope: S) -> ScopeHandle {
+        if self.free.is_empty() {
+            self.grow();
+        }
+        // the index is only taken from `free` once the scope is opened, so that it stays free if `scope` panics.
+        let index = *self.free.last().unwrap();
+        let slot = self.slot_ptr(index);
+
+        // SAFETY: `slot` points to a slot of a live chunk.
+        let raw_scope: *mut RawScope<T, F> = unsafe { addr_of_mut!((*slot).raw_scope) }.cast();
+        // SAFETY: the slot is unoccupied, so `raw_scope` does not hold a scope that would need to be dropped.
+        unsafe {
+            raw_scope
+                .cast::<RawScope<T, MaybeUninit<F>>>()
+                .write(RawScope::<T, F>::new_uninit(()))
+        };
+        // SAFETY:
+        // 1. `raw_scope` points to a slot of a live chunk, although the future is not yet initialized
+        // 2. `raw_scope` was just initialized as a `RawScope::<T, MaybeUninit<F>>`,
+        //    so `state`, `output` and `observer` are fully initialized.
+        //
+        // Note: as a post-condition of `RawScope`, `raw_scope` is fully initialized.
+        unsafe { RawScope::open(raw_scope, scope) };
+
+        self.free.pop();
+        self.len += 1;
+        // SAFETY: `slot` points to a slot of a live chunk.
+        unsafe {
+            (*slot).occupied = true;
+            ScopeHandle {
+                index,
+                generation: (*slot).generation,
+            }
+        }
+    }
+
+    /// Removes the scope of `handle` from the slab, dropping its future.
+    ///
+    /// Returns `false` if the scope was already removed.
+    ///
+    /// # Panics
+    ///
+    /// - If dropping the future of the scope panics. The scope is removed regardless.
+    pub fn remove(&mut self, handle: ScopeHandle) -> bool {
+        let Some(raw_scope) = self.slot(handle) else {
+            return false;
+        };
+        let slot = self.slot_ptr(handle.index);
+        // SAFETY: `slot` points to a slot of a live chunk.
+        let generation = unsafe {
+            (*slot).occupied = false;
+            (*slot).generation.checked_add(1)
+        };
+        // a slot whose generation would wrap is retired, so that its previous handles cannot match a new scope.
+        if let Some(generation) = generation {
+            // SAFETY: `slot` points to a slot of a live chunk.
+            unsafe { (*slot).generation = generation };
+            self.free.push(handle.index);
+        }
+        self.len -= 1;
+
+        // SAFETY: the scope was occupied, so it is fully initialized, and it is no longer reachable from the slab.
+        unsafe { ptr::drop_in_place(raw_scope.as_ptr()) };
+        true
+    }
+
+    /// Allocates a new chunk of unoccupied slots.
+    fn grow(&mut self) {
+        let first = self.chunks.len() * CHUNK_LEN;
+        let chunk: Box<[Slot<T, F>]> = (0..CHUNK_LEN)
+            .map(|_| Slot {
+                generation: 0,
+                occupied: false,
+                raw_scope: MaybeUninit::uninit(),
+            })
+            .collect();
+        // SAFETY: `Box::into_raw` is non-null.
+        self.chunks
+            .push(unsafe { NonNull::new_unchecked(Box::into_raw(chunk)) });
+        self.free.extend((first..first + CHUNK_LEN).rev());
+    }
+
+    /// Returns a pointer to the slot at `index`.
+    ///
+    /// # Panics
+    ///
+    /// - If `index` is out of bounds.
+    fn slot_ptr(&self, index: usize) -> *mut Slot<T, F> {
+        let chunk = self.chunks[index / CHUNK_LEN];
+        // SAFETY: chunks hold `CHUNK_LEN` slots.
+        unsafe { chunk.as_ptr().cast::<Slot<T, F>>().add(index % CHUNK_LEN) }
+    }
+
+    /// Returns a pointer to the scope of `handle`, if it was not removed.
+    fn slot(&self, handle: ScopeHandle) -> Option<NonNull<RawScope<T, F>>> {
+        if handle.index >= self.chunks.len() * CHUNK_LEN {
+            return None;
+        }
+        let slot = self.slot_ptr(handle.index);
+        // SAFETY: `slot` points to a slot of a live chunk.
+        unsafe {
+            ((*slot).occupied && (*slot).generation == handle.generation).then(|| {
+                NonNull::new_unchecked(addr_of_mut!((*slot).raw_scope).cast::<RawScope<T, F>>())
+            })
+        }
+    }
+}
+
+impl<T, F> ScopeSlab<T, F>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    /// Enters the scope of `handle`, making it possible to access the data frozen inside of the scope.
+    ///
+    /// Returns `None` if the scope was removed.
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    /// - If the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    /// - If the scope is poisoned.
+    pub fn enter<'borrow, Output, G>(&'borrow mut self, handle: ScopeHandle, f: G) -> Option<Output>
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        self.enter_with(handle, (), f)
+    }
+}
+
+impl<T, F, I> ScopeSlab<T, F, I>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    /// Enters the scope of `handle`, passing `input` to the scope before accessing the data frozen inside of the scope.
+    ///
+    /// See [`crate::BoxScope::enter_with`]. Returns `None` if the scope was removed.
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    /// - If the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    /// - If the scope is poisoned.
+    pub fn enter_with<'borrow, Output, G>(
+        &'borrow mut self,
+        handle: ScopeHandle,
+        input: I,
+        f: G,
+    ) -> Option<Output>
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        let raw_scope = self.slot(handle)?;
+        // SAFETY:
+        // 1. The slot is occupied, so its scope is fully initialized.
+        // 2. The chunk holding the scope did not move and won't before deallocation.
+        // 3. `ScopeSlab::enter_with` takes an exclusive reference to the slab and returns an exclusive reference
+        //    to the frozen value, so no concurrent calls to `RawScope::enter`.
+        // 4. `insert` only accepts scopes with `I` as input.
+        Some(unsafe { RawScope::enter(raw_scope, input, f) }.into_entered())
+    }
+}
+
+impl<T, F, I> Drop for ScopeSlab<T, F, I>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never>,
+{
+    fn drop(&mut self) {
+        for index in 0..self.chunks.len() * CHUNK_LEN {
+            let slot = self.slot_ptr(index);
+            // SAFETY: `slot` points to a slot of a live chunk, that is fully initialized if occupied.
+            unsafe {
+                if (*slot).occupied {
+                    (*slot).occupied = false;
+                    ptr::drop_in_place(addr_of_mut!((*slot).raw_scope).cast::<RawScope<T, F>>());
+                }
+            }
+        }
+        for chunk in self.chunks.drain(..) {
+            // SAFETY: this `Box::from_raw` pairs with the `Box::into_raw` in `grow`,
+            // and the slots have no drop glue left once their scope is dropped.
+            drop(unsafe { Box::from_raw(chunk.as_ptr()) })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{scope, SingleFamily};
+    use alloc::rc::Rc;
+    use core::cell::Cell;
+
+    fn counter(start: u32, dropped: Rc<Cell<u32>>) -> impl TopScope<Family = SingleFamily<u32>> {
+        scope!({
+            struct SetOnDrop(Rc<Cell<u32>>);
+            impl Drop for SetOnDrop {
+                fn drop(&mut self) {
+                    self.0.set(self.0.get() + 1);
+                }
+            }
+            let _guard = SetOnDrop(dropped);
+            let mut x = start;
+            loop {
+                freeze!(&mut x);
+                x += 1;
+            }
+        })
+    }
+
+    #[test]
+    fn many_chunks() {
+        let dropped = Rc::new(Cell::new(0));
+        let mut slab = ScopeSlab::new();
+        let handles: Vec<_> = (0..200)
+            .map(|i| {
+                let handle = slab.insert(counter(i * 10, dropped.clone()));
+                assert_eq!(slab.enter(handle, |x| *x), Some(i * 10));
+                handle
+            })
+            .collect();
+        assert_eq!(slab.len(), 200);
+
+        for (i, handle) in (0..).zip(&handles) {
+            assert_eq!(slab.enter(*handle, |x| *x), Some(i * 10 + 1));
+        }
+
+        drop(slab);
+        assert_eq!(dropped.get(), 200);
+    }
+
+    #[test]
+    fn remove() {
+        let dropped = Rc::new(Cell::new(0));
+        let mut slab = ScopeSlab::new();
+        let first = slab.insert(counter(0, dropped.clone()));
+        let second = slab.insert(counter(100, dropped.clone()));
+        assert_eq!(slab.enter(first, |x| *x), Some(0));
+
+        assert!(slab.remove(first));
+        assert_eq!(dropped.get(), 1);
+        assert!(!slab.remove(first));
+        assert!(!slab.contains(first));
+        assert_eq!(slab.enter(first, |x| *x), None);
+        assert_eq!(slab.len(), 1);
+
+        // the slot of `first` is reused, but its handle stays invalid.
+        let third = slab.insert(counter(200, dropped.clone()));
+        assert_eq!(third.index, first.index);
+        assert_eq!(slab.enter(first, |x| *x), None);
+        assert_eq!(slab.enter(third, |x| *x), Some(200));
+        assert_eq!(slab.enter(second, |x| *x), Some(100));
+
+        drop(slab);
+        assert_eq!(dropped.get(), 3);
+    }
+
+    #[test]
+    fn retire_last_generation() {
+        let dropped = Rc::new(Cell::new(0));
+        let mut slab = ScopeSlab::new();
+        let first = slab.insert(counter(0, dropped.clone()));
+        // SAFETY: `first` was just inserted, so its slot is in a live chunk.
+        unsafe { (*slab.slot_ptr(first.index)).generation = u32::MAX };
+        let first = ScopeHandle {
+            generation: u32::MAX,
+            ..first
+        };
+        assert_eq!(slab.enter(first, |x| *x), Some(0));
+
+        // the slot of `first` cannot get a new generation, so it is never reused.
+        assert!(slab.remove(first));
+        assert!(!slab.free.contains(&first.index));
+        let second = slab.insert(counter(100, dropped.clone()));
+        assert_ne!(second.index, first.index);
+        assert_eq!(slab.enter(first, |x| *x), None);
+        assert_eq!(slab.enter(second, |x| *x), Some(100));
+
+        drop(slab);
+        assert_eq!(dropped.get(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn panic_while_opening() {
+        let mut slab = ScopeSlab::<SingleFamily<u32>, _>::new();
+        assert!(std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+            slab.insert(unsafe {
+                crate::scope::new_scope(|_time_capsule| {
+                    panic!("panicking producer");
+                    #[allow(unreachable_code, clippy::empty_loop)]
+                    async {
+                        loop {}
+                    }
+                })
+            })
+        }))
+        .is_err());
+        assert!(slab.is_empty());
+        // the slot is still free
+        assert_eq!(slab.free.len(), CHUNK_LEN);
+    }
+}
@@ -0,0 +1,415 @@
+use alloc::boxed::Box;
+use core::{
+    alloc::Layout,
+    future::Future,
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+    ptr::{addr_of_mut, NonNull},
+    task::{Context, Poll},
+};
+
+use crate::{raw_scope::RawScope, waker, Family, Never, ScopeError, ScopeState, TopScope};
+
+/// Polls the future of a scope, see [`RawScope::poll`].
+type PollFn<T, I> = unsafe fn(
+    NonNull<ThinHeader<T, I>>,
+    &mut Option<I>,
+    &mut Context<'_>,
+) -> Poll<Result<(), ScopeError>>;
+
+/// Returns a pointer to a scope, erasing the type of its future.
+type RawScopeFn<T, I> =
+    unsafe fn(NonNull<ThinHeader<T, I>>) -> NonNull<RawScope<T, dyn Future<Output = Never>>>;
+
+/// Operations on the scope of a [`ThinBoxScope`], monomorphized for the type of its future.
+struct ThinVTable<T, I>
+where
+    T: for<'a> Family<'a>,
+    T: 'static,
+    I: 'static,
+{
+    poll: PollFn<T, I>,
+    raw_scope: RawScopeFn<T, I>,
+    /// Drops the scope in place, without freeing its allocation.
+    drop_in_place: unsafe fn(NonNull<ThinHeader<T, I>>),
+    /// The layout of the allocation of the scope.
+    layout: Layout,
+}
+
+/// The start of the allocation of a [`ThinBoxScope`], pointing to the operations for the type of its future.
+struct ThinHeader<T, I>
+where
+    T: for<'a> Family<'a>,
+    T: 'static,
+    I: 'static,
+{
+    vtable: &'static ThinVTable<T, I>,
+}
+
+// SAFETY: repr C so that the header is at the start of the allocation, and to ensure conversion between
+// ThinInner<T, MaybeUninit<F>, I> and ThinInner<T, F, I> does not rely on unstable memory layout.
+#[repr(C)]
+struct ThinInner<T, F, I>
+where
+    T: for<'a> Family<'a>,
+    T: 'static,
+    I: 'static,
+{
+    header: ThinHeader<T, I>,
+    raw_scope: RawScope<T, F>,
+}
+
+impl<T, F, I> ThinInner<T, F, I>
+where
+    T: for<'a> Family<'a>,
+    F: Future<Output = Never> + 'static,
+{
+    const VTABLE: &'static ThinVTable<T, I> = &ThinVTable {
+        poll: Self::poll,
+        raw_scope: Self::erased_raw_scope,
+        drop_in_place: Self::drop_in_place,
+        layout: Layout::new::<Self>(),
+    };
+
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `ThinInner<T, F, I>`.
+    unsafe fn raw_scope(this: NonNull<ThinHeader<T, I>>) -> NonNull<RawScope<T, F>> {
+        let this: *mut Self = this.cast().as_ptr();
+        // SAFETY: precondition (1), and pointers to fields of a valid allocation are non-null.
+        unsafe { NonNull::new_unchecked(addr_of_mut!((*this).raw_scope)) }
+    }
+
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `ThinInner<T, F, I>`.
+    unsafe fn erased_raw_scope(
+        this: NonNull<ThinHeader<T, I>>,
+    ) -> NonNull<RawScope<T, dyn Future<Output = Never>>> {
+        // SAFETY: precondition (1)
+        unsafe { Self::raw_scope(this) }
+    }
+
+    /// # Safety
+    ///
+    /// Same as [`RawScope::poll`], for the scope of the `ThinInner<T, F, I>` pointed to by `this`.
+    unsafe fn poll(
+        this: NonNull<ThinHeader<T, I>>,
+        input: &mut Option<I>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), ScopeError>> {
+        // SAFETY: same preconditions
+        unsafe { RawScope::poll(Self::raw_scope(this), input, cx) }
+    }
+
+    /// # Safety
+    ///
+    /// 1. `this` points to a properly aligned, fully initialized `ThinInner<T, F, I>`.
+    unsafe fn drop_in_place(this: NonNull<ThinHeader<T, I>>) {
+        // SAFETY: precondition (1)
+        unsafe { core::ptr::drop_in_place(this.cast::<Self>().as_ptr()) }
+    }
+}
+
+/// A dynamic scope tied to a Box, whose handle is a single pointer.
+///
+/// Like a [`crate::BoxScope`] erased with [`crate::BoxScope::new_dyn`], the type of the future of the scope is erased,
+/// but the operations on that future are stored at the start of the allocation rather than in the handle.
+/// As a result, a `ThinBoxScope` and an `Option<ThinBoxScope>` have the size of a pointer,
+/// which makes it cheaper to store many scopes in collections.
+///
+/// `I` is the type of the inputs passed to the scope when resuming it, see [`ThinBoxScope::enter_with`].
+///
+/// # Example
+///
+/// ```
+/// use nolife::{scope, SingleFamily, ThinBoxScope};
+///
+/// let mut scopes: Vec<ThinBoxScope<SingleFamily<usize>>> = ["Intel", "the", "Beagle"]
+///     .into_iter()
+///     .map(|s| ThinBoxScope::new(scope!({ freeze_forever!(&mut s.len()) })))
+///     .collect();
+///
+/// assert_eq!(std::mem::size_of::<Option<ThinBoxScope<SingleFamily<usize>>>>(), std::mem::size_of::<usize>());
+/// assert_eq!(scopes[2].enter(|len| *len), 6);
+/// ```
+pub struct ThinBoxScope<T, I = ()>(NonNull<ThinHeader<T, I>>, PhantomData<fn(I)>)
+where
+    T: for<'a> Family<'a>,
+    T: 'static,
+    I: 'static;
+
+impl<T, I> Drop for ThinBoxScope<T, I>
+where
+    T: for<'a> Family<'a>,
+{
+    fn drop(&mut self) {
+        // SAFETY: the scope is valid as a post-condition of `new`.
+        let vtable = unsafe { self.0.as_ref() }.vtable;
+        // SAFETY:
+        // - the scope is valid as a post-condition of `new`, and is dropped only once.
+        // - the allocation was made by a `Box` with the layout stored in the vtable.
+        unsafe {
+            (vtable.drop_in_place)(self.0);
+            alloc::alloc::dealloc(self.0.cast().as_ptr(), vtable.layout);
+        }
+    }
+}
+
+impl<T, I> ThinBoxScope<T, I>
+where
+    T: for<'a> Family<'a>,
+{
+    /// Ties the passed scope to the heap, storing the operations on its future in the same allocation.
+    ///
+    /// # Panics
+    ///
+    /// - If `scope` panics.
+    pub fn new<S: TopScope<I, Family = T>>(scope: S) -> Self
+    where
+        S::Future: 'static,
+    {
+        let inner = Box::new(ThinInner::<T, MaybeUninit<S::Future>, I> {
+            header: ThinHeader {
+                vtable: ThinInner::<T, S::Future, I>::VTABLE,
+            },
+            raw_scope: RawScope::new_uninit(()),
+        });
+        let inner: *mut ThinInner<T, MaybeUninit<S::Future>, I> = Box::into_raw(inner);
+        struct Guard<Sc> {
+            inner: *mut Sc,
+        }
+        // guard ensures Box is freed on panic (i.e. if scope.run panics)
+        let panic_guard = Guard { inner };
+        impl<Sc> Drop for Guard<Sc> {
+            fn drop(&mut self) {
+                // SAFETY: defuse below makes sure this only happens on panic,
+                // in this case, self.inner is still in the same uninitialized state
+                // and not otherwise being cleaned up, so this `Box::from_raw` pairs with
+                // `Box::into_raw` above
+                drop(unsafe { Box::from_raw(self.inner) })
+            }
+        }
+
+        let inner: *mut ThinInner<T, S::Future, I> = inner.cast();
+
+        // SAFETY:
+        // 1. `inner` allocated by the `Box` so is valid memory, although the future is not yet initialized
+        // 2. `inner` was created from a valid `ThinInner::<T, MaybeUninit<S::Future>, I>`,
+        //    so the header and the `state` and `output` of the scope are fully initialized.
+        //
+        // Note: as a post-condition of `RawScope`, the scope is fully initialized.
+        unsafe {
+            RawScope::open(addr_of_mut!((*inner).raw_scope), scope);
+        }
+
+        mem::forget(panic_guard); // defuse guard
+
+        // SAFETY: `inner` allocated by the `Box` so is non-null, and the header is at its start.
+        ThinBoxScope(unsafe { NonNull::new_unchecked(inner.cast()) }, PhantomData)
+    }
+}
+
+impl<T> ThinBoxScope<T>
+where
+    T: for<'a> Family<'a>,
+{
+    /// Enters the scope, making it possible to access the data frozen inside of the scope.
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    /// - If the scope is poisoned, see [`ThinBoxScope::is_poisoned`].
+    /// - If the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    /// - If the underlying future froze its data using `freeze_ref!`.
+    pub fn enter<'borrow, Output, G>(&'borrow mut self, f: G) -> Output
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        self.enter_with((), f)
+    }
+
+    /// Attempts to enter the scope, making it possible to access the data frozen inside of the scope.
+    ///
+    /// See [`crate::BoxScope::try_enter`].
+    ///
+    /// # Errors
+    ///
+    /// - [`ScopeError::Poisoned`] if the scope is poisoned, see [`ThinBoxScope::is_poisoned`].
+    /// - [`ScopeError::ForeignFuture`] if the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    /// - [`ScopeError::NotFilled`] if the underlying future did not fill the value.
+    /// - [`ScopeError::FrozenShared`] if the underlying future froze its data using `freeze_ref!`.
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    pub fn try_enter<'borrow, Output, G>(&'borrow mut self, f: G) -> Result<Output, ScopeError>
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        self.try_enter_with((), f)
+    }
+}
+
+impl<T, I> ThinBoxScope<T, I>
+where
+    T: for<'a> Family<'a>,
+{
+    /// Enters the scope, passing `input` to the scope before accessing the data frozen inside of the scope.
+    ///
+    /// See [`crate::BoxScope::enter_with`].
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    /// - If the scope is poisoned, see [`ThinBoxScope::is_poisoned`].
+    /// - If the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    /// - If the underlying future froze its data using `freeze_ref!`.
+    pub fn enter_with<'borrow, Output, G>(&'borrow mut self, input: I, f: G) -> Output
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        let raw_scope = self.raw_scope();
+        match self.try_enter_with(input, f) {
+            Ok(output) => output,
+            // SAFETY: the scope is valid as a post-condition of `new`.
+            Err(error) => unsafe { RawScope::fail(raw_scope, "enter", error) },
+        }
+    }
+
+    /// Attempts to enter the scope, passing `input` to the scope before accessing the data frozen inside of the scope.
+    ///
+    /// See [`ThinBoxScope::try_enter`] and [`crate::BoxScope::enter_with`].
+    ///
+    /// # Errors
+    ///
+    /// - [`ScopeError::Poisoned`] if the scope is poisoned, see [`ThinBoxScope::is_poisoned`].
+    /// - [`ScopeError::ForeignFuture`] if the underlying future awaits for a future other than the [`crate::FrozenFuture`].
+    /// - [`ScopeError::NotFilled`] if the underlying future did not fill the value.
+    /// - [`ScopeError::FrozenShared`] if the underlying future froze its data using `freeze_ref!`.
+    ///
+    /// # Panics
+    ///
+    /// - If the passed function panics.
+    /// - If the underlying future panics.
+    pub fn try_enter_with<'borrow, Output, G>(
+        &'borrow mut self,
+        input: I,
+        f: G,
+    ) -> Result<Output, ScopeError>
+    where
+        G: for<'a> FnOnce(&'borrow mut <T as Family<'a>>::Family) -> Output,
+    {
+        // If the scope was not frozen yet, nobody takes the input and it is dropped at the end of this function.
+        let mut input = Some(input);
+
+        // SAFETY: the scope is valid as a post-condition of `new`.
+        let vtable = unsafe { self.0.as_ref() }.vtable;
+        // SAFETY:
+        // 1. `self.0` is valid as a post-condition of `new`.
+        // 2. The object pointed to by `self.0` did not move and won't before deallocation.
+        // 3. `&mut self` guarantees exclusive access, and the references passed to previous
+        //    calls to `f` cannot outlive the borrow of the scope that created them.
+        // 4. `new` only accepts scopes with `I` as input.
+        let poll = unsafe {
+            (vtable.poll)(
+                self.0,
+                &mut input,
+                &mut Context::from_waker(&waker::create()),
+            )
+        };
+        match poll {
+            Poll::Ready(Ok(())) => {}
+            Poll::Ready(Err(error)) => return Err(error),
+            Poll::Pending => return Err(ScopeError::ForeignFuture),
+        }
+
+        // SAFETY:
+        // 1. `self.0` is valid as a post-condition of `new`.
+        // 2. `&mut self` guarantees exclusive access for `'borrow`.
+        unsafe { RawScope::access(self.raw_scope(), f) }.map(|outcome| outcome.into_entered())
+    }
+
+    /// Returns `true` if the scope is poisoned, see [`crate::BoxScope::is_poisoned`].
+    pub fn is_poisoned(&self) -> bool {
+        // SAFETY: the scope is valid as a post-condition of `new`.
+        unsafe { RawScope::is_poisoned(self.raw_scope()) }
+    }
+
+    /// Returns the current state of the scope, see [`crate::BoxScope::state`].
+    pub fn state(&self) -> ScopeState {
+        // SAFETY: the scope is valid as a post-condition of `new`.
+        unsafe { RawScope::state(self.raw_scope()) }
+    }
+
+    /// Returns a pointer to the scope, erasing the type of its future.
+    fn raw_scope(&self) -> NonNull<RawScope<T, dyn Future<Output = Never>>> {
+        // SAFETY: the scope is valid as a post-condition of `new`.
+        unsafe { (self.0.as_ref().vtable.raw_scope)(self.0) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{scope, SingleFamily};
+
+    #[test]
+    fn thin_pointer() {
+        assert_eq!(
+            mem::size_of::<ThinBoxScope<SingleFamily<u32>>>(),
+            mem::size_of::<usize>()
+        );
+        assert_eq!(
+            mem::size_of::<Option<ThinBoxScope<SingleFamily<u32>, u32>>>(),
+            mem::size_of::<usize>()
+        );
+    }
+
+    #[test]
+    fn enter_with_input() {
+        let mut scope = ThinBoxScope::<SingleFamily<u32>, u32>::new(scope!(input: u32, {
+            let mut x = 0u32;
+            loop {
+                x += freeze!(&mut x);
+            }
+        }));
+
+        assert_eq!(scope.enter_with(1, |x| *x), 0);
+        assert_eq!(scope.enter_with(2, |x| *x), 2);
+        assert_eq!(scope.enter_with(3, |x| *x), 5);
+        assert_eq!(scope.state(), ScopeState::Frozen);
+    }
+
+    #[test]
+    fn drop_future() {
+        let value = alloc::rc::Rc::new(42u32);
+        let weak = alloc::rc::Rc::downgrade(&value);
+        let mut scope = ThinBoxScope::<SingleFamily<u32>>::new(scope!({
+            let mut x = *value;
+            freeze_forever!(&mut x)
+        }));
+
+        assert_eq!(scope.try_enter(|x| *x), Ok(42));
+        assert!(weak.upgrade().is_some());
+        drop(scope);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn try_enter_freeze_ref() {
+        let mut scope = ThinBoxScope::<SingleFamily<u32>>::new(scope!({
+            let x = 42u32;
+            freeze_ref!(&x);
+            let mut y = 43u32;
+            freeze_forever!(&mut y)
+        }));
+
+        assert_eq!(scope.try_enter(|_| ()), Err(ScopeError::FrozenShared));
+        assert!(!scope.is_poisoned());
+        assert_eq!(scope.try_enter(|x| *x), Ok(43));
+    }
+}
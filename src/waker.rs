@@ -15,3 +15,21 @@ unsafe fn clone(_: *const ()) -> RawWaker {
 unsafe fn wake(_: *const ()) {}
 unsafe fn wake_by_ref(_: *const ()) {}
 unsafe fn drop(_: *const ()) {}
+
+/// Creates a waker that unparks the current thread when woken.
+#[cfg(feature = "std")]
+pub fn current_thread() -> Waker {
+    struct ThreadWaker(std::thread::Thread);
+
+    impl std::task::Wake for ThreadWaker {
+        fn wake(self: std::sync::Arc<Self>) {
+            self.0.unpark()
+        }
+
+        fn wake_by_ref(self: &std::sync::Arc<Self>) {
+            self.0.unpark()
+        }
+    }
+
+    Waker::from(std::sync::Arc::new(ThreadWaker(std::thread::current())))
+}